@@ -0,0 +1,186 @@
+//! collision, line and search speed of the stacked blocks,
+//! next to the scans over colors they used to be
+//!
+//! ~~~bash
+//! cargo bench
+//! ~~~
+
+use divan::{black_box, Bencher};
+use terminal::Color;
+
+use tetris::bot::Bot;
+use tetris::movegen;
+use tetris::{Engine, Event, Point, PointArray, Rules, StackedBlock, BLOCKS, ORIENTATION_COUNT};
+
+fn main() {
+    divan::main();
+}
+
+/// stacked blocks of a game the bot played for a while
+fn midgame() -> Engine {
+    let mut engine = Engine::new(Rules {
+        clear_delay: Default::default(),
+        ..Rules::new(42)
+    });
+    let bot = Bot::default();
+    while engine.pieces() < 25 {
+        for event in bot.think(&engine) {
+            engine.apply(event);
+        }
+    }
+    engine
+}
+
+/// every block in every orientation at every offset inside the walls and above the floor,
+/// the ones a search asks about
+fn placements(stacked: &StackedBlock) -> Vec<PointArray> {
+    let (column, row) = (stacked.get_column() as isize, stacked.get_row() as isize);
+    let mut all = Vec::new();
+    for block in BLOCKS {
+        for orientation in 0..ORIENTATION_COUNT {
+            for x in -3..column {
+                for y in -3..row {
+                    let mut points = *block.points(orientation);
+                    for p in &mut points {
+                        *p = Point::new(p.x + x, p.y + y);
+                    }
+                    if points.iter().all(|p| 0 <= p.x && p.x < column && 0 <= p.y) {
+                        all.push(points);
+                    }
+                }
+            }
+        }
+    }
+    all
+}
+
+/// how the stacked blocks were checked before the bit masks
+mod colors {
+    use super::*;
+
+    /// the grid of colors the bit masks stand for
+    pub fn of(stacked: &StackedBlock) -> Vec<Vec<Color>> {
+        let column = stacked.get_column();
+        stacked
+            .lines()
+            .iter()
+            .map(|line| {
+                (0..column)
+                    .map(|x| match line >> x & 1 {
+                        0 => Color::Reset,
+                        _ => Color::White,
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn fits(colors: &[Vec<Color>], points: &[Point]) -> bool {
+        let col = colors[0].len() as isize;
+        points.iter().all(|p| 0 <= p.x && p.x < col && 0 <= p.y)
+            && !points.iter().any(|p| {
+                (p.y as usize) < colors.len() && colors[p.y as usize][p.x as usize] != Color::Reset
+            })
+    }
+
+    pub fn full_lines(colors: &[Vec<Color>]) -> Vec<usize> {
+        (0..colors.len())
+            .filter(|y| colors[*y].iter().all(|c| *c != Color::Reset))
+            .collect()
+    }
+
+    pub fn eliminate(colors: &mut Vec<Vec<Color>>, lines: &[usize]) {
+        let (row, col) = (colors.len(), colors[0].len());
+        let mut y = 0;
+        colors.retain(|_| {
+            y += 1;
+            !lines.contains(&(y - 1))
+        });
+        colors.resize(row, vec![Color::Reset; col]);
+    }
+}
+
+/// a board with its two lowest lines full
+fn with_full_lines() -> StackedBlock {
+    let mut stacked = midgame().stacked().clone();
+    let column = stacked.get_column() as isize;
+    let floor: Vec<Point> = (0..column)
+        .flat_map(|x| [Point::new(x, 0), Point::new(x, 1)])
+        .collect();
+    stacked.fill(&floor);
+    stacked
+}
+
+#[divan::bench_group]
+mod fits {
+    use super::*;
+
+    #[divan::bench]
+    fn colors(bencher: Bencher) {
+        let stacked = midgame().stacked().clone();
+        let (colors, all) = (super::colors::of(&stacked), placements(&stacked));
+        bencher.bench_local(|| {
+            all.iter()
+                .filter(|p| super::colors::fits(black_box(&colors), *p))
+                .count()
+        });
+    }
+
+    #[divan::bench]
+    fn bits(bencher: Bencher) {
+        let stacked = midgame().stacked().clone();
+        let all = placements(&stacked);
+        bencher.bench_local(|| all.iter().filter(|p| black_box(&stacked).fits(*p)).count());
+    }
+}
+
+#[divan::bench_group]
+mod full_lines {
+    use super::*;
+
+    #[divan::bench]
+    fn colors(bencher: Bencher) {
+        let colors = super::colors::of(&with_full_lines());
+        bencher.bench_local(|| super::colors::full_lines(black_box(&colors)));
+    }
+
+    #[divan::bench]
+    fn bits(bencher: Bencher) {
+        let stacked = with_full_lines();
+        bencher.bench_local(|| black_box(&stacked).full_lines());
+    }
+}
+
+#[divan::bench_group]
+mod eliminate {
+    use super::*;
+
+    #[divan::bench]
+    fn colors(bencher: Bencher) {
+        let colors = super::colors::of(&with_full_lines());
+        bencher
+            .with_inputs(|| colors.clone())
+            .bench_local_values(|mut colors| super::colors::eliminate(&mut colors, &[0, 1]));
+    }
+
+    #[divan::bench]
+    fn bits(bencher: Bencher) {
+        let stacked = with_full_lines();
+        bencher
+            .with_inputs(|| stacked.clone())
+            .bench_local_values(|mut stacked| stacked.eliminate(&vec![0, 1]));
+    }
+}
+
+#[divan::bench]
+fn movegen(bencher: Bencher) {
+    let engine = midgame();
+    bencher.bench_local(|| movegen::generate(engine.stacked(), black_box(engine.current_block())));
+}
+
+#[divan::bench]
+fn bot_think(bencher: Bencher) {
+    let engine = midgame();
+    let bot = Bot::default();
+    bencher.bench_local(|| -> Vec<Event> { bot.think(black_box(&engine)) });
+}
@@ -0,0 +1,143 @@
+//! a tiny bot speaking the tetris bot protocol, to stand in for a real engine:
+//! drops current block straight down wherever the board looks best after it
+
+use tetris::bot::{Features, Weights};
+use tetris::tbp::{BotMessage, FrontendMessage, Move, Orientation, Piece, PieceLocation, Spin};
+use tetris::{Point, StackedBlock, MAX_COLUMN, POINT_OF_BLOCK_COUNT};
+
+use std::io::{self, BufRead, Write};
+
+const ORIENTATIONS: [Orientation; 4] = [
+    Orientation::North,
+    Orientation::East,
+    Orientation::South,
+    Orientation::West,
+];
+
+/// put a piece on the board, and clear the lines it fills
+fn place(stacked: &mut StackedBlock, location: &PieceLocation) -> u32 {
+    stacked.fill(&location.points());
+    let lines = stacked.full_lines();
+    stacked.eliminate(&lines);
+    lines.len() as u32
+}
+
+/// every straight drop of `piece` with its score, best first
+fn suggest(stacked: &StackedBlock, piece: Piece) -> Vec<Move> {
+    let weights = Weights::default();
+    let (column, row) = (stacked.get_column() as isize, stacked.get_row() as isize);
+
+    let mut scored = Vec::new();
+    for orientation in ORIENTATIONS {
+        for x in 0..column {
+            let mut location = PieceLocation {
+                kind: piece,
+                orientation,
+                x,
+                y: row - 3,
+            };
+            if !stacked.fits(&location.points()) {
+                continue;
+            }
+            while stacked.fits(
+                &PieceLocation {
+                    y: location.y - 1,
+                    ..location
+                }
+                .points(),
+            ) {
+                location.y -= 1;
+            }
+
+            let mut stacked = stacked.clone();
+            let lines = place(&mut stacked, &location);
+            let score = Features::new(&stacked, lines).score(&weights);
+            scored.push((
+                score,
+                Move {
+                    location,
+                    spin: Spin::None,
+                },
+            ));
+        }
+    }
+
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().map(|(_, m)| m).collect()
+}
+
+fn send(message: &BotMessage) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{}", serde_json::to_string(message)?)?;
+    stdout.flush()
+}
+
+fn main() -> io::Result<()> {
+    send(&BotMessage::Info {
+        name: String::from("tbp-bot"),
+        version: String::from(env!("CARGO_PKG_VERSION")),
+        author: String::from("tetris"),
+        features: Vec::new(),
+    })?;
+
+    // board and queue of the game started last
+    let mut game: Option<(StackedBlock, Vec<Piece>)> = None;
+
+    for line in io::stdin().lock().lines() {
+        let message = match serde_json::from_str::<FrontendMessage>(&line?) {
+            Ok(message) => message,
+            // unknown messages are ignored, as the protocol asks
+            Err(_) => continue,
+        };
+
+        match message {
+            FrontendMessage::Rules {} => send(&BotMessage::Ready)?,
+            FrontendMessage::Start { queue, board, .. } => {
+                let column = board.first().map_or(0, Vec::len);
+                if !(POINT_OF_BLOCK_COUNT..=MAX_COLUMN).contains(&column)
+                    || board.iter().any(|line| line.len() != column)
+                {
+                    // no suggestion until a board that can be played comes
+                    eprintln!(
+                        "tbp-bot: a board needs {} to {} columns in every row, got {}",
+                        POINT_OF_BLOCK_COUNT, MAX_COLUMN, column
+                    );
+                    game = None;
+                    continue;
+                }
+                let mut stacked = StackedBlock::new(column, board.len());
+                for (y, line) in board.iter().enumerate() {
+                    for (x, cell) in line.iter().enumerate() {
+                        if cell.is_some() {
+                            stacked.fill(&[Point::new(x as isize, y as isize)]);
+                        }
+                    }
+                }
+                game = Some((stacked, queue));
+            }
+            FrontendMessage::Suggest => {
+                let moves = match &game {
+                    Some((stacked, queue)) if !queue.is_empty() => suggest(stacked, queue[0]),
+                    _ => Vec::new(),
+                };
+                send(&BotMessage::Suggestion { moves })?;
+            }
+            FrontendMessage::NewPiece { piece } => {
+                if let Some((_, queue)) = &mut game {
+                    queue.push(piece);
+                }
+            }
+            FrontendMessage::Play { mv } => {
+                if let Some((stacked, queue)) = &mut game {
+                    place(stacked, &mv.location);
+                    if !queue.is_empty() {
+                        queue.remove(0);
+                    }
+                }
+            }
+            FrontendMessage::Stop => game = None,
+            FrontendMessage::Quit => break,
+        }
+    }
+    Ok(())
+}
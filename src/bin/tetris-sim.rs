@@ -0,0 +1,131 @@
+//! plays seeded games with a bot as fast as it thinks, no terminal,
+//! and prints how they went
+
+use tetris::bot::{Bot, Brain, Weights};
+use tetris::{sim, tbp, Rules};
+
+use std::path::Path;
+use std::str::FromStr;
+use std::thread;
+use std::time::Instant;
+
+const USAGE: &str = concat!(
+    "usage: tetris-sim [options]\n",
+    "\n",
+    "options:\n",
+    "    --games <number>       games to play, 100 by default\n",
+    "    --seed <number>        seed of the first game, the next ones count up, 0 by default\n",
+    "    --pieces <number>      stop a game after this many pieces, 10000 by default\n",
+    "    --size <col>x<row>     board size, 10x20 by default\n",
+    "    --speed <number>       speed to play at, 1 by default, gravity drops a row\n",
+    "                           every 2s / speed while each input takes a frame (1/60s)\n",
+    "    --weights <file>       weights of the built-in bot, as written by tetris-train\n",
+    "    --bot <command>        play with a bot speaking the tetris bot protocol instead\n",
+    "    --threads <number>     games played at once, all cores by default\n",
+    "    -h, --help             print this message\n",
+    "\n",
+    "blocks come from the game's own randomizer, each one picked uniformly,\n",
+    "other randomizers are out of scope and can't be compared here",
+);
+
+struct Options {
+    help: bool,
+    games: u64,
+    seed: u64,
+    pieces: u32,
+    rules: Rules,
+    weights: Weights,
+    bot: Option<String>,
+    threads: usize,
+}
+
+/// the value of option `name`, in range of its type
+fn number<T: FromStr>(name: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a number", name))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} `{}`", &name[2..], value))
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options {
+            help: false,
+            games: 100,
+            seed: 0,
+            pieces: 10000,
+            rules: Rules::new(0),
+            weights: Weights::default(),
+            bot: None,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => options.help = true,
+                "--games" => options.games = number("--games", args.next())?,
+                "--seed" => options.seed = number("--seed", args.next())?,
+                "--pieces" => options.pieces = number("--pieces", args.next())?,
+                "--speed" => options.rules.speed = number::<u32>("--speed", args.next())?.max(1),
+                "--threads" => options.threads = number::<usize>("--threads", args.next())?.max(1),
+                "--size" => {
+                    let value = args.next().ok_or("--size needs <col>x<row>")?;
+                    options.rules.size = value
+                        .split_once('x')
+                        .and_then(|(c, r)| Some((c.parse().ok()?, r.parse().ok()?)))
+                        .filter(|(c, r)| {
+                            (tetris::POINT_OF_BLOCK_COUNT..=tetris::MAX_COLUMN).contains(c)
+                                && *r > 0
+                        })
+                        .ok_or_else(|| format!("invalid size `{}`", value))?;
+                }
+                "--weights" => {
+                    let value = args.next().ok_or("--weights needs a file")?;
+                    options.weights = Weights::load(Path::new(&value))?;
+                }
+                "--bot" => {
+                    let value = args.next().ok_or("--bot needs a command")?;
+                    options.bot = Some(value);
+                }
+                _ => return Err(format!("unknown option `{}`\n\n{}", arg, USAGE)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn main() -> Result<(), String> {
+    let print = |s: String| {
+        println!("{}", s);
+        s
+    };
+
+    let options = Options::parse(std::env::args().skip(1)).map_err(print)?;
+    if options.help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let brain = || -> Result<Box<dyn Brain>, String> {
+        Ok(match &options.bot {
+            Some(command) => Box::new(tbp::External::spawn(command)?),
+            None => Box::new(Bot::new(options.weights)),
+        })
+    };
+
+    let start = Instant::now();
+    let seeds = options.seed..options.seed.saturating_add(options.games);
+    let played =
+        sim::run(options.rules, seeds, options.pieces, options.threads, brain).map_err(print)?;
+    let elapsed = start.elapsed();
+
+    println!("{}", sim::Summary::new(&played));
+    println!(
+        "took {:.1?} on {} thread(s), {:.0} pieces per second",
+        elapsed,
+        options.threads,
+        played.iter().map(|g| g.record.pieces as f64).sum::<f64>() / elapsed.as_secs_f64()
+    );
+    Ok(())
+}
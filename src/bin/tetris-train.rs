@@ -0,0 +1,161 @@
+//! evolves the weights of the built-in bot over seeded games, no terminal,
+//! and writes the best ones for `tetris --autoplay --weights` and `tetris-sim --weights`
+
+use tetris::genetic::Evolution;
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread;
+use std::time::Instant;
+
+const USAGE: &str = concat!(
+    "usage: tetris-train [options]\n",
+    "\n",
+    "options:\n",
+    "    --population <number>  weight sets in each generation, 50 by default\n",
+    "    --generations <number> stop after this many generations, 20 by default\n",
+    "    --games <number>       games each weight set plays, 5 by default\n",
+    "    --pieces <number>      stop a game after this many pieces, 500 by default\n",
+    "    --size <col>x<row>     board size to train for, 10x20 by default\n",
+    "    --seed <number>        seed of the first game and of the evolution, 0 by default\n",
+    "    --threads <number>     games played at once, all cores by default\n",
+    "    --checkpoint <file>    progress is saved here after each generation and resumed\n",
+    "                           from if it exists, train.checkpoint by default\n",
+    "    --out <file>           best weights are written here, weights by default\n",
+    "    -h, --help             print this message\n",
+    "\n",
+    "a resumed training keeps the population, games, pieces, size and seed it started with",
+);
+
+struct Options {
+    help: bool,
+    population: usize,
+    generations: u32,
+    games: u64,
+    pieces: u32,
+    size: (usize, usize),
+    seed: u64,
+    threads: usize,
+    checkpoint: PathBuf,
+    out: PathBuf,
+}
+
+/// the value of option `name`, in range of its type
+fn number<T: FromStr>(name: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} needs a number", name))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} `{}`", &name[2..], value))
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options {
+            help: false,
+            population: 50,
+            generations: 20,
+            games: 5,
+            pieces: 500,
+            size: (10, 20),
+            seed: 0,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            checkpoint: PathBuf::from("train.checkpoint"),
+            out: PathBuf::from("weights"),
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => options.help = true,
+                "--population" => {
+                    options.population = number::<usize>("--population", args.next())?.max(2)
+                }
+                "--generations" => options.generations = number("--generations", args.next())?,
+                "--games" => options.games = number::<u64>("--games", args.next())?.max(1),
+                "--pieces" => options.pieces = number("--pieces", args.next())?,
+                "--seed" => options.seed = number("--seed", args.next())?,
+                "--threads" => options.threads = number::<usize>("--threads", args.next())?.max(1),
+                "--size" => {
+                    let value = args.next().ok_or("--size needs <col>x<row>")?;
+                    options.size = value
+                        .split_once('x')
+                        .and_then(|(c, r)| Some((c.parse().ok()?, r.parse().ok()?)))
+                        .filter(|(c, r)| {
+                            (tetris::POINT_OF_BLOCK_COUNT..=tetris::MAX_COLUMN).contains(c)
+                                && *r > 0
+                        })
+                        .ok_or_else(|| format!("invalid size `{}`", value))?;
+                }
+                "--checkpoint" => {
+                    let value = args.next().ok_or("--checkpoint needs a file")?;
+                    options.checkpoint = PathBuf::from(value);
+                }
+                "--out" => {
+                    let value = args.next().ok_or("--out needs a file")?;
+                    options.out = PathBuf::from(value);
+                }
+                _ => return Err(format!("unknown option `{}`\n\n{}", arg, USAGE)),
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn main() -> Result<(), String> {
+    let print = |s: String| {
+        println!("{}", s);
+        s
+    };
+
+    let options = Options::parse(std::env::args().skip(1)).map_err(print)?;
+    if options.help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    let mut evolution = match Evolution::load(&options.checkpoint).map_err(print)? {
+        Some(evolution) => {
+            println!(
+                "resumed {} at generation {}",
+                options.checkpoint.display(),
+                evolution.generation
+            );
+            evolution
+        }
+        None => {
+            let mut evolution = Evolution::new(
+                options.seed,
+                options.size,
+                options.games,
+                options.pieces,
+                options.population,
+            );
+            evolution.evaluate(options.threads).map_err(print)?;
+            evolution.save(&options.checkpoint).map_err(print)?;
+            evolution
+        }
+    };
+
+    loop {
+        let best = *evolution.best().unwrap();
+        println!(
+            "generation {}: best {:.1} lines, weights {:?}",
+            evolution.generation,
+            best.fitness.unwrap(),
+            best.weights.to_array()
+        );
+        best.weights.save(&options.out).map_err(print)?;
+
+        if evolution.generation >= options.generations {
+            break;
+        }
+        let start = Instant::now();
+        evolution.breed();
+        evolution.evaluate(options.threads).map_err(print)?;
+        evolution.save(&options.checkpoint).map_err(print)?;
+        println!("took {:.1?}", start.elapsed());
+    }
+
+    println!("best weights written to {}", options.out.display());
+    Ok(())
+}
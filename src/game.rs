@@ -1,15 +1,44 @@
+use std::io::Write;
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::thread;
 
 use self::frame::Frame;
 
-mod block;
-mod event;
+pub use self::config::Config;
+pub use self::replay::Replay;
+
+mod arena;
+pub(crate) mod block;
+pub(crate) mod bot;
+pub(crate) mod config;
+pub(crate) mod engine;
+pub(crate) mod event;
+pub(crate) mod finesse;
 mod frame;
+pub(crate) mod garbage;
+pub(crate) mod genetic;
 mod handler;
+mod menu;
+pub(crate) mod movegen;
+pub(crate) mod net;
+mod netplay;
 mod painter;
+mod player;
+pub(crate) mod replay;
+pub(crate) mod royale;
+pub(crate) mod save;
+pub(crate) mod scores;
+pub(crate) mod sim;
+pub(crate) mod spectate;
 mod state;
-mod timer;
+pub(crate) mod tbp;
+pub(crate) mod telnet;
 mod trigger;
+mod versus;
+mod viewer;
 
 pub struct Game {
     // definition order is important to drop order
@@ -21,42 +50,444 @@ pub struct Game {
     trigger: trigger::Trigger,
     painter: Arc<painter::Painter>,
     state: Arc<state::State>,
+    /// boards played against the one of `state`, side by side
+    rivals: Vec<Arc<state::State>>,
+    /// the other side of a network game, whose board is the rival
+    peer: Option<net::Peer>,
+    /// the game watched instead of played
+    feed: Option<spectate::Feed>,
+    /// bot boards around the one of `state`, in a battle royale
+    royale: Option<royale::Royale>,
+
+    replay: Option<Replay>,
+    /// the save it goes on from, removed once play begins
+    resumed: bool,
 }
 
 impl Game {
-    pub fn new() -> Self {
+    pub fn new(config: Config, seed: u64) -> Self {
+        let engine = engine::Engine::new(engine::Rules::new(seed));
+        Self::with(painter::Painter::new(), state::State::new(engine, config))
+    }
+
+    /// `state` drawn by `painter` and played alone, each mode adds what it plays with
+    fn with(painter: painter::Painter, state: state::State) -> Self {
         Game {
             handler: Default::default(),
             trigger: Default::default(),
-            painter: Arc::new(painter::Painter::new()),
-            state: Arc::new(state::State::new(
-                10,
-                20,
-                Default::default(),
-                Default::default(),
-            )),
+            painter: Arc::new(painter),
+            state: Arc::new(state),
+            rivals: Vec::new(),
+            peer: None,
+            feed: None,
+            royale: None,
+            replay: None,
+            resumed: false,
         }
     }
 
-    pub fn start(&mut self) -> Result<(), String> {
+    /// place each block on its target with the fewest moves, blocks don't fall on their own
+    pub fn trainer(config: Config, seed: u64) -> Self {
+        let rules = engine::Rules {
+            drop_interval: std::time::Duration::ZERO,
+            ..engine::Rules::new(seed)
+        };
+        let engine = engine::Engine::new(rules);
+
+        let mut trainer = finesse::Trainer::new(seed);
+        trainer.enter(engine.current_block().placement(), rules.size.0);
+        let state = state::State::new(engine, config);
+        *state.trainer.lock().unwrap() = Some(trainer);
+        *state.mode.lock().unwrap() = None;
+
+        Self::with(painter::Painter::new(), state)
+    }
+
+    /// watch a recorded game instead of playing
+    pub fn replay(config: Config, replay: Replay) -> Self {
+        let state = state::State::new(engine::Engine::new(replay.rules), config);
+        *state.mode.lock().unwrap() = None;
+
+        Game {
+            replay: Some(replay),
+            ..Self::with(painter::Painter::new(), state)
+        }
+    }
+
+    /// go on with the saved game, paused
+    pub fn resume(config: Config) -> Result<Self, String> {
+        let state = state::State::new(save::load()?, config);
+        *state.screen.lock().unwrap() = menu::Screen::Pause;
+
+        Ok(Game {
+            resumed: true,
+            ..Self::with(painter::Painter::new(), state)
+        })
+    }
+
+    /// two players side by side on one keyboard, both get the same blocks,
+    /// the left one plays on wasd and the right one on the arrows
+    pub fn versus(config: Config, seed: u64) -> Self {
+        let keymaps = [event::KeyMap::versus_left(), event::KeyMap::versus_right()];
+        let mut states = keymaps.into_iter().enumerate().map(|(i, keymap)| {
+            let seat = state::Seat {
+                index: i as u16,
+                count: 2,
+                name: format!("player {}", i + 1),
+            };
+            Self::seated(
+                Config {
+                    keymap,
+                    ..config.clone()
+                },
+                engine::Rules::new(seed),
+                seat,
+            )
+        });
+
+        let state = states.next().unwrap();
+        Game {
+            rivals: states.map(Arc::new).collect(),
+            ..Self::with(painter::Painter::new(), state)
+        }
+    }
+
+    /// the player on the left against the bot on the right, both get the same blocks
+    pub fn versus_bot(
+        config: Config,
+        seed: u64,
+        weights: bot::Weights,
+        strength: bot::Strength,
+    ) -> Self {
+        let player = state::Seat {
+            index: 0,
+            count: 2,
+            name: config.player(),
+        };
+        let rival = state::Seat {
+            index: 1,
+            count: 2,
+            name: String::from("bot"),
+        };
+        let rival = Self::seated(
+            Config {
+                keymap: event::KeyMap::none(),
+                ..config.clone()
+            },
+            engine::Rules::new(seed),
+            rival,
+        );
+        let mut autoplay = bot::Autoplay::new(Box::new(bot::Rival::new(weights, strength, seed)));
+        autoplay.pps = Some(strength.pps);
+        *rival.autoplay.lock().unwrap() = Some(autoplay);
+
+        let state = Self::seated(config, engine::Rules::new(seed), player);
+        Game {
+            rivals: vec![Arc::new(rival)],
+            ..Self::with(painter::Painter::new(), state)
+        }
+    }
+
+    /// the player against `bots` boards of the built-in bot, all with the same blocks,
+    /// the last one standing wins
+    pub fn royale(
+        config: Config,
+        seed: u64,
+        bots: usize,
+        targeting: royale::Targeting,
+        weights: bot::Weights,
+        strength: bot::Strength,
+    ) -> Self {
+        let rules = engine::Rules::new(seed);
+        let royale = royale::Royale::new(
+            config.player(),
+            targeting,
+            rules,
+            &config.garbage,
+            bots,
+            weights,
+            strength,
+        );
+        let state = state::State::new(engine::Engine::new(rules), config);
+        *state.mode.lock().unwrap() = None;
+
+        Game {
+            royale: Some(royale),
+            ..Self::with(painter::Painter::new(), state)
+        }
+    }
+
+    /// wait on `addr` for a player to join over the network, blocks go by `seed`
+    pub fn host(config: Config, seed: u64, addr: &str) -> Result<Self, String> {
+        let peer = net::Peer::host(addr, &config.player(), seed, &config.garbage)?;
+        Ok(Self::netplay(config, seed, peer))
+    }
+
+    /// join a game hosted at `addr`, its seed and attack table are played by
+    pub fn join(config: Config, addr: &str) -> Result<Self, String> {
+        let (peer, seed, garbage) = net::Peer::join(addr, &config.player())?;
+        Ok(Self::netplay(Config { garbage, ..config }, seed, peer))
+    }
+
+    fn netplay(config: Config, seed: u64, peer: net::Peer) -> Self {
+        let player = state::Seat {
+            index: 0,
+            count: 2,
+            name: config.player(),
+        };
+        let rival = state::Seat {
+            index: 1,
+            count: 2,
+            name: peer.name.clone(),
+        };
+        // the board of the peer moves only by its messages
+        let rules = engine::Rules {
+            drop_interval: std::time::Duration::ZERO,
+            ..engine::Rules::new(seed)
+        };
+        let rival = Self::seated(
+            Config {
+                keymap: event::KeyMap::none(),
+                ..config.clone()
+            },
+            rules,
+            rival,
+        );
+
+        let state = Self::seated(config, engine::Rules::new(seed), player);
+        Game {
+            rivals: vec![Arc::new(rival)],
+            peer: Some(peer),
+            ..Self::with(painter::Painter::new(), state)
+        }
+    }
+
+    /// a board of a versus game, kept out of the high score tables
+    fn seated(config: Config, rules: engine::Rules, seat: state::Seat) -> state::State {
+        let engine = engine::Engine::new(rules);
+        let mut state = state::State::new(engine, config);
+        state.seat = seat;
+        *state.mode.lock().unwrap() = None;
+        state
+    }
+
+    /// watch the game broadcast at `addr`, it shows as it is when it comes in
+    pub fn spectate(config: Config, addr: &str) -> Result<Self, String> {
+        let feed = spectate::Feed::connect(addr)?;
+        let engine = match feed.recv_timeout(spectate::TIMEOUT) {
+            Some(Ok(spectate::Message::Snapshot { save })) => engine::Engine::restore(&save)?,
+            Some(Ok(message)) => return Err(format!("expect snapshot, got {:?}", message)),
+            Some(Err(e)) => return Err(e),
+            None => return Err(format!("{} sends no snapshot", feed.name)),
+        };
+
+        let mut state = state::State::new(engine, config);
+        state.spectator = true;
+        *state.mode.lock().unwrap() = None;
+
+        Ok(Game {
+            feed: Some(feed),
+            ..Self::with(painter::Painter::new(), state)
+        })
+    }
+
+    /// a game of its own for each telnet client connecting on `addr`, up to `telnet::MAX_CLIENTS`
+    /// at once, blocks go by `seed` if one is given
+    pub fn serve(config: Config, addr: &str, seed: Option<u64>) -> Result<(), String> {
+        let listener = TcpListener::bind(addr).map_err(|e| format!("{}: {}", addr, e))?;
+        println!(
+            "serving telnet games on {}, {} at most at once",
+            addr,
+            telnet::MAX_CLIENTS
+        );
+        let clients = Arc::new(AtomicUsize::new(0));
+        for mut stream in listener.incoming().flatten() {
+            let addr = match stream.peer_addr() {
+                Ok(addr) => addr.to_string(),
+                Err(_) => String::from("a client"),
+            };
+            if clients.fetch_add(1, Ordering::Relaxed) >= telnet::MAX_CLIENTS {
+                clients.fetch_sub(1, Ordering::Relaxed);
+                println!("🚫 {} turned away, {} playing", addr, telnet::MAX_CLIENTS);
+                stream
+                    .write_all(b"all games are taken, come back later\r\n")
+                    .ok();
+                continue;
+            }
+
+            let config = config.clone();
+            let seed = seed.unwrap_or_else(rand::random);
+            let clients = clients.clone();
+            thread::spawn(move || {
+                println!("🔌 {} joined, seed {}", addr, seed);
+                match Self::remote(config, seed, stream) {
+                    Ok(summary) => println!("👋 {} left, {}", addr, summary),
+                    Err(e) => println!("👋 {} left, {}", addr, e),
+                }
+                clients.fetch_sub(1, Ordering::Relaxed);
+            });
+        }
+        Ok(())
+    }
+
+    /// play one game with the telnet client on `stream`, then tell it how it went
+    fn remote(config: Config, seed: u64, stream: TcpStream) -> Result<String, String> {
+        let error = |e: std::io::Error| e.to_string();
+        stream.set_nodelay(true).map_err(error)?;
+        let keyboard = telnet::Keyboard::open(&stream)?;
+        let sink = stream.try_clone().map_err(error)?;
+
+        let mut state = state::State::new(engine::Engine::new(engine::Rules::new(seed)), config);
+        state.remote = true;
+        *state.mode.lock().unwrap() = None;
+
+        let mut game = Self::with(painter::Painter::remote(Box::new(sink), keyboard), state);
+        let state = game.state.clone();
+        let started = game.start();
+        // the game is over with its handler, closing the keyboard ends the trigger too
+        game.handler = Default::default();
+        stream.shutdown(Shutdown::Read).ok();
+        drop(game);
+
+        let engine::Record {
+            secs,
+            line,
+            score,
+            pieces,
+            ..
+        } = *state.engine.lock().unwrap().record();
+        let summary = format!(
+            "score {}, {} line(s), {} piece(s) in {} second(s)",
+            score, line, pieces, secs
+        );
+        let message = match started {
+            Err(e) => e,
+            Ok(()) => match state.message.lock().unwrap().take() {
+                Some(message) => format!("{}\r\n😃 {}", message, summary),
+                None => format!("😃 {}", summary),
+            },
+        };
+        // a client that left early doesn't hear it
+        (&stream)
+            .write_all(format!("{}\r\n", message).as_bytes())
+            .ok();
+        Ok(summary)
+    }
+
+    /// stream this game to viewers connecting at `addr`, `unix:<path>` for a unix socket
+    pub fn broadcast(&self, addr: &str) -> Result<(), String> {
+        let broadcast =
+            spectate::Broadcast::listen(addr, &self.state.config.lock().unwrap().player())?;
+        *self.state.broadcast.lock().unwrap() = Some(broadcast);
+        Ok(())
+    }
+
+    /// save every applied event of this game to `path`
+    pub fn record(&self, path: &Path) -> Result<(), String> {
+        let recorder = replay::Recorder::create(path, &self.state.rules())?;
+        *self.state.recorder.lock().unwrap() = Some(recorder);
+        Ok(())
+    }
+
+    /// let a bot play, kept out of the high score tables
+    pub fn autoplay(&self, brain: Box<dyn bot::Brain>) {
+        *self.state.autoplay.lock().unwrap() = Some(bot::Autoplay::new(brain));
+        *self.state.mode.lock().unwrap() = None;
+    }
+
+    fn check_terminal_size(&self) -> Result<(), String> {
         if let Err(((col, c), (row, r))) = {
             frame::GameFrame::flush_terminal_size(&self.painter);
             frame::GameFrame::test_terminal_size(&self.state)
         } {
-            return Err(format!(
+            Err(format!(
                 concat!(
                     "terminal screen (column x row) too small: ",
                     "current screen is {}x{} , ",
                     "need at least {}x{}"
                 ),
                 col, row, c, r
-            ));
+            ))
+        } else if let Some(Err((c, r))) = self
+            .royale
+            .as_ref()
+            .map(|royale| frame::RivalsFrame::slots(&self.state, royale.opponents.len()))
+        {
+            let (col, row) = frame::GameFrame::get_terminal_size();
+            Err(format!(
+                concat!(
+                    "terminal screen (column x row) too small for {} bot(s): ",
+                    "current screen is {}x{} , ",
+                    "need at least {}x{}"
+                ),
+                self.royale.as_ref().unwrap().opponents.len(),
+                col,
+                row,
+                c,
+                r
+            ))
         } else {
-            let (timer_rx, event_rx) = self.trigger.start(self.painter.clone(), self.state.clone());
-            self.handler
-                .start(self.painter.clone(), self.state.clone(), timer_rx, event_rx);
+            Ok(())
+        }
+    }
 
-            self.state.timer.resume();
+    /// leave the game before it begins, it is kept out of the high score tables
+    pub fn abort(&self) {
+        *self.state.mode.lock().unwrap() = None;
+    }
+
+    pub fn start(&mut self) -> Result<(), String> {
+        // a game that can't begin keeps its save
+        if let Err(e) = self
+            .check_terminal_size()
+            .and_then(|()| match self.resumed {
+                true => save::remove(),
+                false => Ok(()),
+            })
+        {
+            self.abort();
+            return Err(e);
+        }
+        self.resumed = false;
+
+        if let Some(feed) = self.feed.take() {
+            let event_rx = self.trigger.start(self.painter.clone());
+            self.handler
+                .start_viewer(self.painter.clone(), self.state.clone(), feed, event_rx);
+            Ok(())
+        } else if let Some(peer) = self.peer.take() {
+            let event_rx = self.trigger.start(self.painter.clone());
+            self.handler.start_netplay(
+                self.painter.clone(),
+                self.state.clone(),
+                self.rivals[0].clone(),
+                peer,
+                event_rx,
+            );
+            Ok(())
+        } else if let Some(royale) = self.royale.take() {
+            let event_rx = self.trigger.start(self.painter.clone());
+            self.handler
+                .start_royale(self.painter.clone(), self.state.clone(), royale, event_rx);
+            Ok(())
+        } else if !self.rivals.is_empty() {
+            let event_rx = self.trigger.start(self.painter.clone());
+            let states = std::iter::once(&self.state)
+                .chain(&self.rivals)
+                .cloned()
+                .collect();
+            self.handler
+                .start_versus(self.painter.clone(), states, event_rx);
+            Ok(())
+        } else if let Some(replay) = self.replay.take() {
+            let event_rx = self.trigger.start(self.painter.clone());
+            self.handler
+                .start_replay(self.painter.clone(), self.state.clone(), replay, event_rx);
+            Ok(())
+        } else {
+            let event_rx = self.trigger.start(self.painter.clone());
+            self.handler
+                .start(self.painter.clone(), self.state.clone(), event_rx);
             Ok(())
         }
     }
@@ -0,0 +1,208 @@
+use super::event;
+use super::frame::{self, Frame};
+use super::handler::{self, Handler};
+use super::royale::{self, Royale};
+use super::{menu, painter, state};
+
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use terminal::{Event, KeyCode};
+
+/// time between two inputs of each bot, their pieces per second are kept by `Autoplay::pps`
+const RIVAL_INTERVAL: Duration = Duration::from_millis(30);
+
+/// a battle royale: the board of the player in the middle, bot boards small around it,
+/// all on one clock; keys 1 to 4 pick the targeting of the player when they are not bound
+pub struct Arena {
+    state: Arc<state::State>,
+    royale: Royale,
+    /// where each bot board is drawn
+    slots: Vec<(u16, u16)>,
+}
+
+impl Arena {
+    pub fn new(state: Arc<state::State>, royale: Royale) -> Self {
+        *state.royale.lock().unwrap() = Some(royale.status());
+        Arena {
+            state,
+            royale,
+            slots: Vec::new(),
+        }
+    }
+
+    pub fn run(mut self, event_rx: Receiver<Event>, painter: &Arc<painter::Painter>) {
+        self.state.engine.lock().unwrap().take_changes();
+        self.resize(painter);
+        painter.flush().unwrap();
+
+        let mut last = Instant::now();
+        let mut think = last;
+        while !self.state.quit() {
+            let raw = match event_rx.recv_timeout(handler::TICK) {
+                Ok(raw) => Some(raw),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            // every board goes by the same clock
+            let now = Instant::now();
+            if self.is_playing() {
+                let millis = Duration::from_millis(now.duration_since(last).as_millis() as u64);
+                last += millis;
+                self.state.engine.lock().unwrap().tick(millis);
+
+                let thinking = now >= think;
+                if thinking {
+                    think = now + RIVAL_INTERVAL;
+                }
+                let engine = self.state.engine.lock().unwrap();
+                if let Err(e) = self.royale.tick(millis, thinking, &engine) {
+                    drop(engine);
+                    self.stop(format!("bot fails: {}", e));
+                }
+            } else {
+                last = now;
+            }
+
+            if let Some(raw) = raw {
+                self.input(raw, painter);
+            }
+
+            let over = Handler::update(painter, &self.state);
+            let sent = self.state.sent.swap(0, Ordering::Relaxed);
+            let out = {
+                let mut engine = self.state.engine.lock().unwrap();
+                self.royale.update(&mut engine, sent, over)
+            };
+            self.draw(painter);
+            if !out.is_empty() && self.royale.is_over() {
+                self.finish();
+            }
+            painter.flush().unwrap();
+        }
+    }
+
+    fn is_playing(&self) -> bool {
+        matches!(*self.state.screen.lock().unwrap(), menu::Screen::Game)
+    }
+
+    /// bot boards that changed, and the status if it did
+    fn draw(&mut self, painter: &Arc<painter::Painter>) {
+        let status = self.royale.status();
+        if self.state.royale.lock().unwrap().as_ref() != Some(&status) {
+            *self.state.royale.lock().unwrap() = Some(status);
+            frame::RoyaleFrame::draw_inner(painter, &self.state);
+        }
+
+        // the pause covers the board of the player only
+        for (i, slot) in self.slots.iter().enumerate() {
+            if self.royale.opponents[i].dirty {
+                self.royale.opponents[i].dirty = false;
+                frame::RivalsFrame::draw_mini(painter, &self.royale, i, *slot);
+            }
+        }
+    }
+
+    /// the player went out or is the last one standing, the places are told
+    fn finish(&self) {
+        let players = &self.royale.players;
+        let player = &players[0];
+        let total = players.len();
+        let end = match (player.place, player.attacker) {
+            (Some(place), Some(by)) => format!(
+                "🏁 {} knocked out by {}, #{} of {}",
+                player.name, players[by].name, place, total
+            ),
+            (Some(place), None) => {
+                format!("🏁 {} topped out, #{} of {}", player.name, place, total)
+            }
+            (None, _) => format!("🏆 {} wins, the last of {} standing", player.name, total),
+        };
+        let order: Vec<&str> = self
+            .royale
+            .order
+            .iter()
+            .map(|i| players[*i].name.as_str())
+            .collect();
+        self.stop(format!(
+            "{}\n🎖️ {} KO(s), {} badge point(s)\n💀 out in order: {}",
+            end,
+            player.kos,
+            player.badges,
+            order.join(", ")
+        ));
+    }
+
+    fn stop(&self, message: String) {
+        let mut shown = self.state.message.lock().unwrap();
+        // the first reason is the one to show
+        if shown.is_none() {
+            *shown = Some(message);
+        }
+        self.state.quit_signal.store(true, Ordering::Relaxed);
+    }
+
+    fn resize(&mut self, painter: &Arc<painter::Painter>) {
+        frame::GameFrame::flush_terminal_size(painter);
+        let slots = match frame::GameFrame::test_terminal_size(&self.state) {
+            Ok(_) => frame::RivalsFrame::slots(&self.state, self.royale.opponents.len()),
+            Err(((_, c), (_, r))) => Err((c, r)),
+        };
+        self.slots = match slots {
+            Ok(slots) => slots,
+            Err((col, row)) => {
+                let (c, r) = frame::GameFrame::get_terminal_size();
+                return self.stop(format!(
+                    "terminal screen (column x row) too small for {} bot(s): current screen is {}x{} , need at least {}x{}",
+                    self.royale.opponents.len(),
+                    c,
+                    r,
+                    col,
+                    row
+                ));
+            }
+        };
+
+        painter.clear_all().unwrap();
+        Handler::draw_frames(painter, &self.state);
+        for opponent in &mut self.royale.opponents {
+            opponent.dirty = true;
+        }
+        self.draw(painter);
+    }
+
+    /// keys play the board of the player, pause stops every board
+    fn input(&mut self, raw: Event, painter: &Arc<painter::Painter>) {
+        if let Event::Resize = raw {
+            return self.resize(painter);
+        }
+
+        let event = self.state.config.lock().unwrap().keymap.get(&raw);
+        match (event, raw) {
+            (event::Event::Quit, _) => self.state.quit_signal.store(true, Ordering::Relaxed),
+            (event::Event::Toggle, _) => self.toggle(painter),
+            (event::Event::Unknow, Event::Key(key)) if self.is_playing() => {
+                if let KeyCode::Char(c @ '1'..='4') = key.code {
+                    let targeting = royale::TARGETINGS[c as usize - '1' as usize];
+                    let engine = self.state.engine.lock().unwrap();
+                    self.royale.set_targeting(targeting, &engine);
+                }
+            }
+            (event, _) if self.is_playing() => Handler::apply(event, painter, &self.state),
+            _ => {}
+        }
+    }
+
+    fn toggle(&self, painter: &Arc<painter::Painter>) {
+        if self.is_playing() {
+            *self.state.screen.lock().unwrap() = menu::Screen::Pause;
+            frame::GameFrame::draw_pause(painter, &self.state);
+        } else {
+            *self.state.screen.lock().unwrap() = menu::Screen::Game;
+            frame::GameFrame::draw_inner(painter, &self.state);
+        }
+    }
+}
@@ -1,13 +1,14 @@
-use std::ops::{AddAssign, Index, IndexMut};
+use rand::Rng;
+use std::ops::{AddAssign, Index};
 use terminal::Color;
 
 pub const POINT_OF_BLOCK_COUNT: usize = 4;
 pub const ORIENTATION_COUNT: usize = 4;
 
-type PointArray = [Point; POINT_OF_BLOCK_COUNT];
+pub type PointArray = [Point; POINT_OF_BLOCK_COUNT];
 type PointMatrix = [PointArray; ORIENTATION_COUNT];
 
-#[derive(Default, Clone, Copy)]
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Point {
     pub x: isize,
     pub y: isize,
@@ -52,6 +53,15 @@ pub struct Block {
 }
 
 impl Block {
+    pub(crate) fn color(&self) -> &Color {
+        &self.color
+    }
+
+    /// points of the block turned to `orientation`, at where it is created
+    pub fn points(&self, orientation: usize) -> &PointArray {
+        &self.point_matrix[orientation]
+    }
+
     const fn new(color: Color, point_matrix: PointMatrix) -> Self {
         Block {
             color,
@@ -78,6 +88,8 @@ impl Block {
     }
 }
 
+// each number is `10 * x + y`, leading zeros keep the columns aligned
+#[allow(clippy::zero_prefixed_literal)]
 pub const BLOCKS: [Block; 7] = [
     // OrangeRicky J
     Block::new_n(
@@ -151,16 +163,37 @@ pub const BLOCKS: [Block; 7] = [
     ),
 ];
 
+/// index of the T block in BLOCKS
+pub const T_KIND: usize = 5;
+
+/// widest board, a line of stacked blocks is one `u32`
+pub const MAX_COLUMN: usize = u32::BITS as usize;
+
+/// tallest board a save or replay may ask for, they come from files and the network
+pub const MAX_ROW: usize = 1000;
+
+/// color of garbage lines, no block has it
+pub const GARBAGE_COLOR: Color = Color::Grey;
+
+/// blocks that are down: a bit mask for each line to find collisions and full lines fast,
+/// and their colors only to draw them
+#[derive(Clone)]
 pub struct StackedBlock {
-    pub colors: Vec<Vec<Color>>,
+    lines: Vec<u32>, // bit x of line y is set when (x, y) is covered
+    colors: Vec<Vec<Color>>,
+    column: usize,
+    full: u32, // mask of a full line
 }
 
 impl StackedBlock {
     pub fn new(column: usize, row: usize) -> Self {
-        debug_assert!(row > 0 && column > 0);
+        debug_assert!(row > 0 && column > 0 && column <= MAX_COLUMN);
         let default_color = Color::Reset;
         StackedBlock {
-            colors: vec![vec![default_color; column as usize]; row as usize],
+            lines: vec![0; row],
+            colors: vec![vec![default_color; column]; row],
+            column,
+            full: u32::MAX >> (MAX_COLUMN - column),
         }
     }
 
@@ -172,17 +205,44 @@ impl StackedBlock {
     }
 
     pub fn get_row(&self) -> usize {
-        self.colors.len()
+        self.lines.len()
     }
 
     pub fn get_column(&self) -> usize {
-        self.colors.last().unwrap().len()
+        self.column
+    }
+
+    /// bit mask of a full line
+    pub fn full(&self) -> u32 {
+        self.full
+    }
+
+    /// bit masks from the floor, bit x is set when column x is covered
+    pub fn lines(&self) -> &[u32] {
+        &self.lines
+    }
+
+    /// colors from the floor, `Color::Reset` where nothing is
+    pub(crate) fn colors(&self) -> &[Vec<Color>] {
+        &self.colors
     }
 
-    pub fn cover(&mut self, color: Color, points: &[Point]) {
+    /// cover with garbage, for boards built outside a game
+    pub fn fill(&mut self, points: &[Point]) {
+        self.cover(GARBAGE_COLOR, points);
+    }
+
+    /// `Color::Reset` uncovers
+    pub(crate) fn cover(&mut self, color: Color, points: &[Point]) {
         for p in points {
             if self.is_valid_index(p) {
-                self[p] = color;
+                let (x, y) = (p.x as usize, p.y as usize);
+                self.colors[y][x] = color;
+                if let Color::Reset = color {
+                    self.lines[y] &= !(1 << x);
+                } else {
+                    self.lines[y] |= 1 << x;
+                }
             }
         }
     }
@@ -190,7 +250,21 @@ impl StackedBlock {
     pub fn is_overlapped(&self, points: &[Point]) -> bool {
         points
             .iter()
-            .any(|p| self.is_valid_index(p) && self[p] != Color::Reset)
+            .any(|p| self.is_valid_index(p) && self.lines[p.y as usize] & 1 << p.x != 0)
+    }
+
+    /// inside the walls and above the floor, not on stacked blocks
+    #[inline]
+    pub fn fits(&self, points: &[Point]) -> bool {
+        points.iter().all(|p| {
+            // left of the wall or below the floor is too far as usize
+            (p.x as usize) < self.column
+                && match self.lines.get(p.y as usize) {
+                    Some(line) => line & 1 << p.x == 0,
+                    // anywhere above the lines is empty
+                    None => 0 <= p.y,
+                }
+        })
     }
 
     // pub fn stack(&mut self, color: Color, points: &[Point]) -> bool {
@@ -203,16 +277,10 @@ impl StackedBlock {
     // }
 
     pub fn full_lines(&self) -> Vec<usize> {
-        self.colors
+        self.lines
             .iter()
             .enumerate()
-            .filter_map(|(i, line)| {
-                if line.iter().all(|x| *x != Color::Reset) {
-                    Some(i)
-                } else {
-                    None
-                }
-            })
+            .filter_map(|(i, line)| if *line == self.full { Some(i) } else { None })
             .collect()
     }
 
@@ -225,18 +293,37 @@ impl StackedBlock {
 
     pub fn eliminate(&mut self, lines: &Vec<usize>) {
         debug_assert!(*lines == self.full_lines(), "eliminate non-full lines");
-        let mut full = vec![true; self.get_row()];
-        for line in lines.iter() {
-            full[*line] = false;
-        }
 
-        let mut iter = full.iter();
-        self.colors.retain(|_| *iter.next().unwrap());
+        // lines left move down in place, the rows of colors are reused on top
+        let mut kept = 0;
+        for y in 0..self.get_row() {
+            if !lines.contains(&y) {
+                self.lines[kept] = self.lines[y];
+                self.colors.swap(kept, y);
+                kept += 1;
+            }
+        }
+        for y in kept..self.get_row() {
+            self.lines[y] = 0;
+            self.colors[y].fill(Color::Reset);
+        }
+    }
 
-        self.colors.resize(
-            self.colors.len() + lines.len(),
-            vec![Color::Reset; self.get_column()],
-        );
+    /// push `count` garbage lines in from the floor, full but for column `hole`,
+    /// false if stacked blocks are pushed out of the top
+    pub fn raise(&mut self, count: usize, hole: usize) -> bool {
+        let row = self.get_row();
+        let count = count.min(row);
+        let fits = self.lines[row - count..].iter().all(|line| *line == 0);
+
+        self.lines.rotate_right(count);
+        self.colors.rotate_right(count);
+        for y in 0..count {
+            self.lines[y] = self.full & !(1 << hole);
+            self.colors[y].fill(GARBAGE_COLOR);
+            self.colors[y][hole] = Color::Reset;
+        }
+        fits
     }
 }
 
@@ -246,41 +333,61 @@ impl Index<&Point> for StackedBlock {
     fn index(&self, point: &Point) -> &Self::Output {
         let Point { x, y } = *point;
         let (ux, uy) = (x as usize, y as usize);
-        debug_assert!(self.is_valid_index(&point));
+        debug_assert!(self.is_valid_index(point));
         &self.colors[uy][ux]
     }
 }
 
-impl IndexMut<&Point> for StackedBlock {
-    fn index_mut(&mut self, point: &Point) -> &mut Self::Output {
-        let Point { x, y } = *point;
-        let (ux, uy) = (x as usize, y as usize);
-        debug_assert!(self.is_valid_index(&point));
-        &mut self.colors[uy][ux]
+/// where a block is: its kind, orientation and how far it is moved from where it is created
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Placement {
+    pub kind: usize, // index in BLOCKS
+    pub orientation: usize,
+    pub offset: Point,
+}
+
+impl Placement {
+    pub fn block(&self) -> FallingBlock {
+        let mut block = FallingBlock::new(self.kind, self.orientation);
+        block.shift(&self.offset);
+        block
+    }
+
+    pub fn points(&self) -> PointArray {
+        let mut points = *BLOCKS[self.kind].points(self.orientation);
+        points.add_assign(&self.offset);
+        points
     }
 }
 
+#[derive(Clone)]
 pub struct FallingBlock {
+    kind: usize, // index in BLOCKS
     block: Block,
     orientation: usize,
 }
 
-impl Default for FallingBlock {
-    fn default() -> Self {
-        let x = rand::random::<usize>() % (BLOCKS.len() * ORIENTATION_COUNT);
-        Self::new(x / ORIENTATION_COUNT, x % ORIENTATION_COUNT)
-    }
-}
-
 impl FallingBlock {
     pub fn new(block_idx: usize, orientation: usize) -> Self {
         debug_assert!(block_idx < BLOCKS.len() && orientation < ORIENTATION_COUNT);
         FallingBlock {
+            kind: block_idx,
             block: BLOCKS[block_idx],
             orientation,
         }
     }
 
+    /// move back to where the block was created, keep the orientation
+    pub fn reset(&mut self) {
+        self.block = BLOCKS[self.kind];
+    }
+
+    /// any block in any orientation
+    pub fn random<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let x = rng.gen_range(0..BLOCKS.len() * ORIENTATION_COUNT);
+        Self::new(x / ORIENTATION_COUNT, x % ORIENTATION_COUNT)
+    }
+
     pub fn shift(&mut self, p: &Point) {
         for points in self.block.point_matrix.iter_mut() {
             for point in points.iter_mut() {
@@ -289,7 +396,33 @@ impl FallingBlock {
         }
     }
 
-    pub fn color(&self) -> &Color {
+    /// index in BLOCKS
+    pub fn kind(&self) -> usize {
+        self.kind
+    }
+
+    pub fn orientation(&self) -> usize {
+        self.orientation
+    }
+
+    /// how far the block is moved from where it was created
+    pub fn offset(&self) -> Point {
+        let (p, q) = (
+            self.block.point_matrix[0][0],
+            BLOCKS[self.kind].point_matrix[0][0],
+        );
+        Point::new(p.x - q.x, p.y - q.y)
+    }
+
+    pub fn placement(&self) -> Placement {
+        Placement {
+            kind: self.kind,
+            orientation: self.orientation,
+            offset: self.offset(),
+        }
+    }
+
+    pub(crate) fn color(&self) -> &Color {
         &self.block.color
     }
 
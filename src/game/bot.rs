@@ -0,0 +1,417 @@
+use super::block::{Placement, PointArray, StackedBlock, BLOCKS};
+use super::engine::Engine;
+use super::event::Event;
+use super::movegen;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// how much each board feature is worth, higher is better
+///
+/// ~~~text
+/// # written by tetris-train
+/// height = -0.510066
+/// lines = 0.760666
+/// holes = -0.35663
+/// bumpiness = -0.184483
+/// ~~~
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Weights {
+    pub height: f32,
+    pub lines: f32,
+    pub holes: f32,
+    pub bumpiness: f32,
+}
+
+impl Default for Weights {
+    /// tuned by a genetic algorithm, from yiyuan lee's tetris ai
+    fn default() -> Self {
+        Weights {
+            height: -0.510066,
+            lines: 0.760666,
+            holes: -0.35663,
+            bumpiness: -0.184483,
+        }
+    }
+}
+
+impl Weights {
+    pub const NAMES: [&'static str; 4] = ["height", "lines", "holes", "bumpiness"];
+
+    pub fn to_array(self) -> [f32; 4] {
+        [self.height, self.lines, self.holes, self.bumpiness]
+    }
+
+    pub fn from_array([height, lines, holes, bumpiness]: [f32; 4]) -> Self {
+        Weights {
+            height,
+            lines,
+            holes,
+            bumpiness,
+        }
+    }
+
+    /// weights not given keep their default
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut weights = Weights::default().to_array();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (name, value) = line
+                .split_once('=')
+                .map(|(n, v)| (n.trim(), v.trim()))
+                .ok_or_else(|| format!("line {}: expect `name = value`", i + 1))?;
+            let index = Self::NAMES
+                .iter()
+                .position(|n| *n == name)
+                .ok_or_else(|| format!("line {}: unknown weight `{}`", i + 1, name))?;
+            weights[index] = value
+                .parse()
+                .map_err(|_| format!("line {}: invalid weight `{}`", i + 1, value))?;
+        }
+
+        Ok(Self::from_array(weights))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let s = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&s).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_string()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+impl fmt::Display for Weights {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, weight) in Self::NAMES.iter().zip(self.to_array()) {
+            writeln!(f, "{} = {}", name, weight)?;
+        }
+        Ok(())
+    }
+}
+
+/// board features after a block is placed
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Features {
+    pub height: u32,    // sum of column heights
+    pub lines: u32,     // lines cleared by the block
+    pub holes: u32,     // empty cells below the top of their column
+    pub bumpiness: u32, // sum of height differences of neighbour columns
+}
+
+impl Features {
+    /// of stacked blocks, `lines` just cleared
+    pub fn new(stacked: &StackedBlock, lines: u32) -> Self {
+        let (column, row) = (stacked.get_column(), stacked.get_row());
+        let mut heights = vec![0; column];
+        let mut holes = 0;
+
+        let masks = stacked.lines();
+        for (x, height) in heights.iter_mut().enumerate() {
+            let filled = |y: usize| masks[y] & 1 << x != 0;
+            *height = (0..row).rev().find(|y| filled(*y)).map_or(0, |y| y + 1);
+            holes += (0..*height).filter(|y| !filled(*y)).count();
+        }
+
+        Features {
+            height: heights.iter().sum::<usize>() as u32,
+            lines,
+            holes: holes as u32,
+            bumpiness: heights
+                .windows(2)
+                .map(|w| w[0].abs_diff(w[1]))
+                .sum::<usize>() as u32,
+        }
+    }
+
+    pub fn score(&self, weights: &Weights) -> f32 {
+        weights.height * self.height as f32
+            + weights.lines * self.lines as f32
+            + weights.holes * self.holes as f32
+            + weights.bumpiness * self.bumpiness as f32
+    }
+}
+
+/// where a placement comes to rest when dropped straight down
+pub fn landing(stacked: &StackedBlock, placement: &Placement) -> PointArray {
+    movegen::rest(stacked, placement).points()
+}
+
+/// a board a block can leave, with the lines it clears, where it lands and the inputs to it
+type Board = (StackedBlock, u32, PointArray, Vec<Event>);
+
+/// every board `block` can leave, a block that would stay out of sight is left out
+fn boards(stacked: &StackedBlock, block: Placement) -> Vec<Board> {
+    let row = stacked.get_row() as isize;
+    let color = *BLOCKS[block.kind].color();
+
+    movegen::generate(stacked, &block.block())
+        .into_iter()
+        .filter_map(|lock| {
+            if lock.points.iter().any(|p| p.y >= row) {
+                return None;
+            }
+
+            let mut stacked = stacked.clone();
+            stacked.cover(color, &lock.points);
+            let lines = stacked.full_lines();
+            stacked.eliminate(&lines);
+            Some((stacked, lines.len() as u32, lock.points, lock.inputs))
+        })
+        .collect()
+}
+
+/// plays by itself: tries every placement of current and next block,
+/// and keeps the best board they can leave
+#[derive(Clone, Copy, Debug)]
+pub struct Bot {
+    pub weights: Weights,
+    pub depth: usize, // blocks looked at, 1 for current block only or 2 with next
+}
+
+impl Default for Bot {
+    fn default() -> Self {
+        Bot::new(Weights::default())
+    }
+}
+
+impl Bot {
+    pub fn new(weights: Weights) -> Self {
+        Bot { weights, depth: 2 }
+    }
+
+    /// where current block locks at its best and the inputs to it, None if it can't lock
+    pub fn best(&self, engine: &Engine) -> Option<(PointArray, Vec<Event>)> {
+        let next = Placement {
+            offset: engine.spawn_point(),
+            ..engine.next_block().placement()
+        };
+
+        let mut best: Option<(f32, PointArray, Vec<Event>)> = None;
+        for (stacked, lines, points, inputs) in
+            boards(engine.stacked(), engine.current_block().placement())
+        {
+            if self.depth < 2 {
+                let score = Features::new(&stacked, lines).score(&self.weights);
+                if best.as_ref().is_none_or(|(s, ..)| score > *s) {
+                    best = Some((score, points, inputs));
+                }
+                continue;
+            }
+
+            let score = boards(&stacked, next)
+                .iter()
+                .map(|(stacked, more, ..)| {
+                    Features::new(stacked, lines + more).score(&self.weights)
+                })
+                .max_by(f32::total_cmp)
+                // next block can't come in after it
+                .unwrap_or(f32::MIN);
+
+            if best.as_ref().is_none_or(|(s, ..)| score > *s) {
+                best = Some((score, points, inputs));
+            }
+        }
+
+        best.map(|(_, points, inputs)| (points, inputs))
+    }
+
+    /// inputs that place current block at its best, hard drop last
+    pub fn think(&self, engine: &Engine) -> Vec<Event> {
+        self.best(engine)
+            .map_or_else(|| vec![Event::HardDrop], |(_, inputs)| inputs)
+    }
+}
+
+/// points in order, to compare where blocks are
+fn sorted(mut points: PointArray) -> PointArray {
+    points.sort_by_key(|p| (p.x, p.y));
+    points
+}
+
+/// inputs that lock a block at `target`, hard drop last,
+/// holding first if `hold`, None if it can't get there
+pub fn path(engine: &Engine, hold: bool, target: &PointArray) -> Option<Vec<Event>> {
+    let from = if !hold {
+        engine.current_block().placement()
+    } else if engine.can_hold() {
+        let block = engine.hold_block().unwrap_or(engine.next_block());
+        Placement {
+            offset: engine.spawn_point(),
+            ..block.placement()
+        }
+    } else {
+        return None;
+    };
+
+    let target = sorted(*target);
+    let lock = movegen::generate(engine.stacked(), &from.block())
+        .into_iter()
+        .find(|lock| lock.points == target)?;
+
+    let mut inputs = Vec::new();
+    if hold {
+        inputs.push(Event::Hold);
+    }
+    inputs.extend(lock.inputs);
+    Some(inputs)
+}
+
+/// anything that finds the inputs for current block
+pub trait Brain: Send {
+    fn think(&mut self, engine: &Engine) -> Result<Vec<Event>, String>;
+}
+
+impl Brain for Bot {
+    fn think(&mut self, engine: &Engine) -> Result<Vec<Event>, String> {
+        Ok(Bot::think(self, engine))
+    }
+}
+
+/// how well the bot plays as a rival
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Strength {
+    pub pps: f32,      // pieces per second at most
+    pub depth: usize,  // blocks looked at, as `Bot::depth`
+    pub mistakes: f64, // chance a block goes anywhere it can instead of its best
+}
+
+pub const LEVELS: [&str; 3] = ["easy", "normal", "hard"];
+
+impl Strength {
+    /// one of LEVELS
+    pub fn level(name: &str) -> Option<Self> {
+        let (pps, depth, mistakes) = match name {
+            "easy" => (0.7, 1, 0.15),
+            "normal" => (1.2, 2, 0.05),
+            "hard" => (2.5, 2, 0.0),
+            _ => return None,
+        };
+        Some(Strength {
+            pps,
+            depth,
+            mistakes,
+        })
+    }
+}
+
+/// the bot held back by a strength, the pace is kept by `Autoplay::pps`
+pub struct Rival {
+    bot: Bot,
+    mistakes: f64,
+    rng: ChaCha8Rng,
+}
+
+impl Rival {
+    /// same `seed` makes the same mistakes
+    pub fn new(weights: Weights, strength: Strength, seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(2);
+        Rival {
+            bot: Bot {
+                weights,
+                depth: strength.depth,
+            },
+            mistakes: strength.mistakes,
+            rng,
+        }
+    }
+}
+
+impl Brain for Rival {
+    fn think(&mut self, engine: &Engine) -> Result<Vec<Event>, String> {
+        if self.rng.gen_bool(self.mistakes) {
+            let boards = boards(engine.stacked(), engine.current_block().placement());
+            if let Some((.., inputs)) = boards.choose(&mut self.rng) {
+                return Ok(inputs.clone());
+            }
+        }
+        Ok(self.bot.think(engine))
+    }
+}
+
+/// a brain playing a live game, its inputs wait in `plan`
+pub struct Autoplay {
+    brain: Box<dyn Brain>,
+    /// each input with where current block should be before it,
+    /// None until the brain thinks about current block
+    pub plan: Option<VecDeque<(Placement, Event)>>,
+    /// pieces per second at most, by the game clock
+    pub pps: Option<f32>,
+}
+
+impl Autoplay {
+    pub fn new(brain: Box<dyn Brain>) -> Self {
+        Autoplay {
+            brain,
+            plan: None,
+            pps: None,
+        }
+    }
+
+    /// next input for `engine`, None to wait
+    pub fn next(&mut self, engine: &Engine) -> Result<Option<Event>, String> {
+        if engine.is_over() || engine.clearing().is_some() {
+            return Ok(None);
+        }
+        // a new block waits its turn
+        if self.plan.is_none()
+            && self
+                .pps
+                .is_some_and(|pps| engine.clock().as_secs_f32() * pps < engine.pieces() as f32)
+        {
+            return Ok(None);
+        }
+        let plan = match &mut self.plan {
+            Some(plan) => plan,
+            None => {
+                let mut ahead = engine.clone();
+                let plan = self.brain.think(engine)?.into_iter().map(|event| {
+                    let before = ahead.current_block().placement();
+                    ahead.apply(event);
+                    (before, event)
+                });
+                self.plan.insert(plan.collect())
+            }
+        };
+
+        let current = engine.current_block().placement();
+        // gravity may have done some of the soft drops
+        while let Some((before, Event::Drop)) = plan.front() {
+            if before.offset.y <= current.offset.y {
+                break;
+            }
+            plan.pop_front();
+        }
+
+        match plan.pop_front() {
+            // a block that fell lower than planned goes on from there
+            Some((before, event))
+                if before.kind == current.kind
+                    && before.orientation == current.orientation
+                    && before.offset.x == current.offset.x
+                    && before.offset.y >= current.offset.y =>
+            {
+                Ok(Some(event))
+            }
+            // off the plan, think again
+            Some(_) => {
+                self.plan = None;
+                Ok(None)
+            }
+            None => Ok(None),
+        }
+    }
+}
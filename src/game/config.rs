@@ -0,0 +1,207 @@
+use super::event::{self, Key, KeyMap};
+use super::garbage::Garbage;
+
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// settings read from `$XDG_CONFIG_HOME/tetris/config`
+///
+/// ~~~text
+/// # a preset goes first, following lines rebind single actions
+/// preset = vim
+/// name = violet
+/// hold = c, shift+c
+/// quit = q, ctrl+c
+/// # garbage sent in versus, see `Garbage`
+/// attack_lines = 0, 1, 2, 4
+/// garbage_messiness = 0.3
+/// ~~~
+#[derive(Clone, Default)]
+pub struct Config {
+    pub keymap: KeyMap,
+    pub name: Option<String>, // in the high score tables
+    pub garbage: Garbage,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .map(|dir| dir.join("tetris").join("config"))
+    }
+
+    /// name in the high score tables, the login name if not set
+    pub fn player(&self) -> String {
+        self.name
+            .clone()
+            .or_else(|| env::var("USER").ok().filter(|s| !s.is_empty()))
+            .unwrap_or_else(|| String::from("player"))
+    }
+
+    /// saved game and scores go here, `$XDG_DATA_HOME/tetris`
+    pub fn data_dir() -> Option<PathBuf> {
+        env::var_os("XDG_DATA_HOME")
+            .filter(|s| !s.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+            })
+            .map(|dir| dir.join("tetris"))
+    }
+
+    /// missing config file gives the default config
+    pub fn load() -> Result<Self, String> {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Ok(Default::default()),
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(s) => Self::parse(&s).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Default::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    /// write every binding out, comments in the old file are lost
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or_else(|| String::from("no config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        fs::write(&path, self.to_string()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut config = Config::default();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (name, value) = line
+                .split_once('=')
+                .map(|(n, v)| (n.trim(), v.trim()))
+                .ok_or_else(|| format!("line {}: expect `name = value`", i + 1))?;
+
+            if name == "preset" {
+                config.keymap = KeyMap::preset(value).ok_or_else(|| {
+                    format!(
+                        "line {}: unknown preset `{}`, expect one of {}",
+                        i + 1,
+                        value,
+                        event::PRESETS.join(", ")
+                    )
+                })?;
+            } else if name == "name" {
+                config.name = Some(String::from(value)).filter(|n| !n.is_empty());
+            } else if let Some(event) = event::Event::from_name(name) {
+                let keys = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|k| !k.is_empty())
+                    .map(str::parse::<Key>)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("line {}: {}", i + 1, e))?;
+                config
+                    .keymap
+                    .bind(event, keys)
+                    .map_err(|e| format!("line {}: {}", i + 1, e))?;
+            } else if !config
+                .garbage
+                .set(name, value)
+                .map_err(|e| format!("line {}: {}", i + 1, e))?
+            {
+                return Err(format!("line {}: unknown setting `{}`", i + 1, name));
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+impl fmt::Display for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.name {
+            writeln!(f, "name = {}", name)?;
+        }
+        for event in event::ACTIONS {
+            let keys = self.keymap.keys(event);
+            let keys: Vec<String> = keys.iter().map(Key::to_string).collect();
+            writeln!(f, "{} = {}", event.name(), keys.join(", "))?;
+        }
+        if self.garbage != Garbage::default() {
+            write!(f, "{}", self.garbage)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::event::Event;
+
+    #[test]
+    fn preset_then_single_actions() {
+        let config = Config::parse(
+            "# comment\n\
+             preset = vim\n\
+             name = violet # trailing comment\n\
+             hold = c, shift+c\n\
+             attack_lines = 0, 1, 3, 5\n",
+        )
+        .unwrap();
+
+        let key = |s: &str| s.parse::<Key>().unwrap();
+        assert_eq!(config.keymap.keys(Event::Left), [key("h")]);
+        assert_eq!(config.keymap.keys(Event::Hold), [key("c"), key("C")]);
+        assert_eq!(config.name.as_deref(), Some("violet"));
+        assert_eq!(config.garbage.lines, [0, 1, 3, 5]);
+    }
+
+    #[test]
+    fn vim_keys_add_to_any_preset() {
+        for preset in event::PRESETS {
+            let config = Config::parse(&format!("preset = {}\nleft = left, h", preset)).unwrap();
+            assert_eq!(config.keymap.conflicts(), [], "{}", preset);
+        }
+    }
+
+    #[test]
+    fn saved_config_reads_back() {
+        let mut config = Config {
+            name: Some(String::from("violet")),
+            ..Default::default()
+        };
+        config
+            .keymap
+            .bind(Event::Hold, vec!["ctrl+h".parse().unwrap()])
+            .unwrap();
+        config.keymap.bind(Event::Hint, vec![]).unwrap();
+        config.garbage.messiness = 0.5;
+
+        let read = Config::parse(&config.to_string()).unwrap();
+        assert_eq!(read.to_string(), config.to_string());
+        assert_eq!(read.keymap.keys(Event::Hint), []);
+        assert_eq!(read.garbage, config.garbage);
+    }
+
+    #[test]
+    fn errors_tell_the_line() {
+        let error = |s: &str| Config::parse(s).err().unwrap();
+        assert!(error("\npreset = emacs").starts_with("line 2: unknown preset `emacs`"));
+        assert!(error("hold c").starts_with("line 1: expect `name = value`"));
+        assert!(error("hold = hyper+c").starts_with("line 1: unknown modifier"));
+        assert!(error("quit = p\ntoggle =").starts_with("line 2: toggle keeps one key"));
+        assert!(error("jump = j").starts_with("line 1: unknown setting `jump`"));
+        assert!(error("attack_lines = 1").starts_with("line 1: expect 4 numbers"));
+    }
+}
@@ -0,0 +1,806 @@
+use super::block::{self, FallingBlock, Placement, Point, StackedBlock};
+use super::event::Event;
+use super::garbage::{Clear, Garbage, Spin};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use std::collections::VecDeque;
+use std::ops::AddAssign;
+use std::time::Duration;
+
+mod save;
+
+const TO_LEFT_POINT: Point = Point::new(-1, 0);
+const TO_RIGHT_POINT: Point = Point::new(1, 0);
+const TO_DROP_POINT: Point = Point::new(0, -1);
+
+pub const DEFAULT_DROP_INTERVAL: Duration = Duration::from_secs(2);
+/// full lines blink twice before they are removed
+pub const DEFAULT_CLEAR_DELAY: Duration = Duration::from_millis(1600);
+
+fn line_to_score(line: u32) -> u32 {
+    (1..=line).sum()
+}
+
+/// rng of garbage holes for a game of `seed`
+fn garbage_rng(seed: u64) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(1);
+    rng
+}
+
+/// settings a game needs to be played again
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rules {
+    pub seed: u64,
+    pub size: (usize, usize), // (column, row)
+    pub speed: u32,
+    pub drop_interval: Duration, // at speed 1, zero for no gravity
+    pub clear_delay: Duration,   // zero removes full lines at once
+}
+
+impl Rules {
+    pub fn new(seed: u64) -> Self {
+        Rules {
+            seed,
+            size: (10, 20),
+            speed: 1,
+            drop_interval: DEFAULT_DROP_INTERVAL,
+            clear_delay: DEFAULT_CLEAR_DELAY,
+        }
+    }
+
+    pub fn gravity_interval(&self) -> Duration {
+        self.drop_interval / self.speed
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Record {
+    pub secs: u32, // seconds
+    pub line: u32,
+    pub score: u32,
+    pub speed: u32,
+    pub pieces: u32, // blocks locked
+    pub keys: u32,   // inputs taken, gravity not counted
+    pub attack: u32, // garbage lines sent
+}
+
+impl Default for Record {
+    fn default() -> Self {
+        Record {
+            secs: 0,
+            line: 0,
+            score: 0,
+            speed: 1,
+            pieces: 0,
+            keys: 0,
+            attack: 0,
+        }
+    }
+}
+
+/// rates worked out from record and time of play
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stats {
+    pub pps: f32, // pieces per second
+    pub kpp: f32, // keys per piece
+    pub lpm: f32, // lines per minute
+    pub apm: f32, // attack per minute
+}
+
+#[derive(Clone)]
+pub(crate) struct TwoBlocks {
+    curr: Box<FallingBlock>,
+    next: Box<FallingBlock>,
+    hold: Option<Box<FallingBlock>>,
+    held: bool, // current block came from hold
+}
+
+impl TwoBlocks {
+    pub fn new(curr: Box<FallingBlock>, next: Box<FallingBlock>) -> Self {
+        TwoBlocks {
+            curr,
+            next,
+            hold: None,
+            held: false,
+        }
+    }
+
+    pub fn current_block(&self) -> &FallingBlock {
+        &self.curr
+    }
+
+    pub fn current_block_mut(&mut self) -> &mut FallingBlock {
+        &mut self.curr
+    }
+
+    pub fn next_block(&self) -> &FallingBlock {
+        &self.next
+    }
+
+    pub fn hold_block(&self) -> Option<&FallingBlock> {
+        self.hold.as_deref()
+    }
+
+    pub fn can_hold(&self) -> bool {
+        !self.held
+    }
+
+    pub fn push(&mut self, mut block: Box<FallingBlock>) -> Box<FallingBlock> {
+        use std::mem::swap;
+        swap(&mut self.curr, &mut self.next);
+        swap(&mut self.next, &mut block);
+        self.held = false;
+        block
+    }
+
+    /// exchange current and held blocks, false if nothing is held
+    pub fn swap_hold(&mut self) -> bool {
+        if let Some(hold) = &mut self.hold {
+            std::mem::swap(&mut self.curr, hold);
+            hold.reset();
+            self.held = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn set_hold(&mut self, mut block: Box<FallingBlock>) {
+        block.reset();
+        self.hold = Some(block);
+        self.held = true;
+    }
+}
+
+/// what happened inside engine, frontends draw from these
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    Applied(Duration, Event), // an input taken at that time of play, gravity included
+    Moved,                    // current block moved or rotated
+    Entered(Placement),       // current block came in at the top, from next or hold
+    Locked(Placement),        // current block joined the stacked blocks there
+    Full(Vec<usize>),         // full lines start to clear
+    Cleared(u32),             // full lines removed, record changed
+    Spawned,                  // current and next block changed
+    Held,                     // hold block changed
+    Second(u32),              // time of play reached a new second
+    Sent(u32),                // garbage lines a clear sends to an opponent
+    Pending(u32),             // garbage lines waiting to rise changed to this many
+    Risen(u32),               // garbage lines pushed the stacked blocks up
+    Over,                     // block stack overflow
+}
+
+/// the whole game without terminal, threads or wall clock
+///
+/// inputs go in by `apply`, time goes in by `tick`,
+/// the same rules, inputs and times always give the same game
+#[derive(Clone)]
+pub struct Engine {
+    rules: Rules,
+
+    /// every block comes from here, same seed gives same blocks
+    rng: ChaCha8Rng,
+    /// holes of garbage lines, apart from blocks so garbage doesn't change them
+    garbage_rng: ChaCha8Rng,
+    garbage: Garbage,
+    incoming: VecDeque<u32>, // garbage attacks to rise when a block locks without a clear
+
+    combo: u32,         // clears in a row so far
+    back_to_back: bool, // the last clear was a tetris or t-spin
+    rotated: bool,      // the last move of current block was a rotation
+    blocks: TwoBlocks,
+    stacked: StackedBlock,
+
+    record: Record,
+
+    clock: Duration,                          // time of play
+    gravity: Duration,                        // time since the last drop
+    clearing: Option<(Vec<usize>, Duration)>, // full lines and how long they are shown
+    over: bool,
+
+    changes: Vec<Change>,
+}
+
+impl Engine {
+    pub fn new(rules: Rules) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(rules.seed);
+        let curr = FallingBlock::random(&mut rng);
+        let next = FallingBlock::random(&mut rng);
+
+        let mut engine = Engine {
+            rules,
+            rng,
+            garbage_rng: garbage_rng(rules.seed),
+            garbage: Garbage::default(),
+            incoming: VecDeque::new(),
+            combo: 0,
+            back_to_back: false,
+            rotated: false,
+            blocks: TwoBlocks::new(Box::new(curr), Box::new(next)),
+            stacked: StackedBlock::new(rules.size.0, rules.size.1),
+            record: Record {
+                speed: rules.speed,
+                ..Default::default()
+            },
+            clock: Duration::ZERO,
+            gravity: Duration::ZERO,
+            clearing: None,
+            over: false,
+            changes: Vec::new(),
+        };
+        engine.generate_new_block();
+        engine
+    }
+
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub fn record(&self) -> &Record {
+        &self.record
+    }
+
+    /// blocks locked so far
+    pub fn pieces(&self) -> u32 {
+        self.record.pieces
+    }
+
+    /// clears in a row so far
+    pub fn combo(&self) -> u32 {
+        self.combo
+    }
+
+    /// the last clear was a tetris or t-spin
+    pub fn back_to_back(&self) -> bool {
+        self.back_to_back
+    }
+
+    pub fn stats(&self) -> Stats {
+        let Record {
+            line,
+            pieces,
+            keys,
+            attack,
+            ..
+        } = self.record;
+        let secs = self.clock.as_secs_f32();
+        let per_second = |n: u32| if secs > 0.0 { n as f32 / secs } else { 0.0 };
+
+        Stats {
+            pps: per_second(pieces),
+            kpp: if pieces > 0 {
+                keys as f32 / pieces as f32
+            } else {
+                0.0
+            },
+            lpm: per_second(line) * 60.0,
+            apm: per_second(attack) * 60.0,
+        }
+    }
+
+    pub fn clock(&self) -> Duration {
+        self.clock
+    }
+
+    pub fn stacked(&self) -> &StackedBlock {
+        &self.stacked
+    }
+
+    pub fn current_block(&self) -> &FallingBlock {
+        self.blocks.current_block()
+    }
+
+    pub fn next_block(&self) -> &FallingBlock {
+        self.blocks.next_block()
+    }
+
+    pub fn hold_block(&self) -> Option<&FallingBlock> {
+        self.blocks.hold_block()
+    }
+
+    /// false if current block came from hold
+    pub fn can_hold(&self) -> bool {
+        self.blocks.can_hold()
+    }
+
+    /// full lines waiting to be removed, and how long they have been shown
+    pub fn clearing(&self) -> Option<(&[usize], Duration)> {
+        self.clearing
+            .as_ref()
+            .map(|(lines, shown)| (&lines[..], *shown))
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.over
+    }
+
+    pub fn garbage(&self) -> &Garbage {
+        &self.garbage
+    }
+
+    /// how clears attack and garbage rises from now on
+    pub fn set_garbage(&mut self, garbage: Garbage) {
+        self.garbage = garbage;
+    }
+
+    /// garbage lines waiting to rise
+    pub fn pending_garbage(&self) -> u32 {
+        self.incoming.iter().sum()
+    }
+
+    /// an attack from an opponent, it waits behind earlier ones until a block locks
+    /// without a clear, clears cancel it first
+    pub fn receive(&mut self, lines: u32) {
+        if self.over || lines == 0 {
+            return;
+        }
+        self.incoming.push_back(lines);
+        self.changes.push(Change::Pending(self.pending_garbage()));
+    }
+
+    /// changes since the last call
+    pub fn take_changes(&mut self) -> Vec<Change> {
+        std::mem::take(&mut self.changes)
+    }
+
+    pub fn is_valid_position(&self, points: &[Point]) -> bool {
+        self.stacked.fits(points)
+    }
+
+    /// where blocks come in, as the offset of their placement
+    pub fn spawn_point(&self) -> Point {
+        let (col, row) = self.rules.size;
+        Point::new(
+            (col - block::POINT_OF_BLOCK_COUNT) as isize / 2,
+            row as isize - 1,
+        )
+    }
+
+    /// false when the input is not taken: not a game input,
+    /// lines are clearing or the game is over
+    pub fn apply(&mut self, event: Event) -> bool {
+        if self.over || self.clearing.is_some() {
+            return false;
+        }
+
+        let apply: fn(&mut Self) = match event {
+            Event::Left => |e| e.translate(&TO_LEFT_POINT),
+            Event::Right => |e| e.translate(&TO_RIGHT_POINT),
+            Event::ClockRotate => |e| e.rotate(1),
+            Event::InverseRotate => |e| e.rotate(-1),
+            Event::FlipRotate => |e| e.rotate(2),
+            Event::Hold => Self::hold,
+            Event::Drop | Event::Gravity => Self::drop,
+            Event::HardDrop => Self::hard_drop,
+            _ => return false,
+        };
+
+        if event != Event::Gravity {
+            self.record.keys += 1;
+        }
+        self.changes.push(Change::Applied(self.clock, event));
+        apply(self);
+        true
+    }
+
+    /// let `dt` of play pass, blocks fall and full lines clear on their own
+    pub fn tick(&mut self, mut dt: Duration) {
+        while !dt.is_zero() && !self.over {
+            let gravity = !self.rules.drop_interval.is_zero();
+            let left = match &self.clearing {
+                Some((_, shown)) => self.rules.clear_delay.saturating_sub(*shown),
+                None if gravity => self.rules.gravity_interval().saturating_sub(self.gravity),
+                None => dt,
+            };
+            let step = left.min(dt);
+            dt -= step;
+            self.advance(step);
+
+            if let Some((lines, shown)) = &mut self.clearing {
+                *shown += step;
+                if *shown >= self.rules.clear_delay {
+                    let lines = std::mem::take(lines);
+                    self.clearing = None;
+                    self.clear(&lines);
+                }
+            } else if gravity {
+                self.gravity += step;
+                if self.gravity >= self.rules.gravity_interval() {
+                    self.changes
+                        .push(Change::Applied(self.clock, Event::Gravity));
+                    self.drop();
+                }
+            }
+        }
+    }
+
+    fn advance(&mut self, step: Duration) {
+        self.clock += step;
+
+        let secs = self.clock.as_secs() as u32;
+        if secs != self.record.secs {
+            self.record.secs = secs;
+            self.changes.push(Change::Second(secs));
+        }
+    }
+
+    fn translate(&mut self, to: &Point) {
+        let mut points = *self.current_block().points();
+        points.add_assign(to);
+
+        if self.is_valid_position(&points) {
+            self.blocks.current_block_mut().shift(to);
+            self.rotated = false;
+            self.changes.push(Change::Moved);
+        }
+    }
+
+    fn rotate(&mut self, direction: isize) {
+        let mut block = self.current_block().clone();
+        block += direction;
+
+        if self.is_valid_position(block.points()) {
+            *self.blocks.current_block_mut() = block;
+            self.rotated = true;
+            self.changes.push(Change::Moved);
+        }
+    }
+
+    fn drop(&mut self) {
+        self.gravity = Duration::ZERO;
+
+        let mut points = *self.current_block().points();
+        points.add_assign(&TO_DROP_POINT);
+
+        if self.is_valid_position(&points) {
+            // likely
+            self.blocks.current_block_mut().shift(&TO_DROP_POINT);
+            self.rotated = false;
+            self.changes.push(Change::Moved);
+        } else {
+            // unlikely
+            self.lock();
+        }
+    }
+
+    fn hard_drop(&mut self) {
+        let mut points = *self.current_block().points();
+        let mut fall = 0;
+        while {
+            points.add_assign(&TO_DROP_POINT);
+            self.is_valid_position(&points)
+        } {
+            fall += 1;
+        }
+        self.blocks.current_block_mut().shift(&Point::new(0, -fall));
+        if fall > 0 {
+            self.rotated = false;
+        }
+
+        self.drop();
+    }
+
+    fn hold(&mut self) {
+        if !self.blocks.can_hold() {
+            return;
+        }
+
+        if self.blocks.swap_hold() {
+            self.spawn();
+        } else {
+            let block = self.generate_new_block();
+            self.blocks.set_hold(block);
+        }
+
+        self.gravity = Duration::ZERO;
+        self.changes.push(Change::Held);
+        self.changes.push(Change::Moved);
+    }
+
+    fn lock(&mut self) {
+        let spin = self.spin();
+        let block = self.current_block();
+        let (color, points, placement) = (*block.color(), *block.points(), block.placement());
+        self.stacked.cover(color, &points);
+        self.record.pieces += 1;
+        self.changes.push(Change::Locked(placement));
+
+        let full_lines = self.stacked.full_lines();
+        if full_lines.is_empty() {
+            self.combo = 0;
+            if !self.incoming.is_empty() && !self.rise() {
+                return;
+            }
+            self.generate_new_block();
+            return;
+        }
+
+        let perfect = self
+            .stacked
+            .lines()
+            .iter()
+            .enumerate()
+            .all(|(y, line)| *line == 0 || full_lines.contains(&y));
+        let clear = Clear {
+            lines: full_lines.len() as u32,
+            spin,
+            back_to_back: false,
+            combo: self.combo,
+            perfect,
+        };
+        let clear = Clear {
+            back_to_back: self.back_to_back && clear.is_difficult(),
+            ..clear
+        };
+        self.back_to_back = clear.is_difficult();
+        self.combo += 1;
+        self.attack(&clear);
+
+        if self.rules.clear_delay.is_zero() {
+            self.clear(&full_lines);
+        } else {
+            self.changes.push(Change::Full(full_lines.clone()));
+            self.clearing = Some((full_lines, Duration::ZERO));
+        }
+    }
+
+    fn clear(&mut self, lines: &Vec<usize>) {
+        let line = lines.len() as u32;
+        self.stacked.eliminate(lines);
+        self.record.line += line;
+        self.record.score += line_to_score(line);
+        self.changes.push(Change::Cleared(line));
+
+        self.generate_new_block();
+    }
+
+    /// t-spin of current block if it locked now: a t turned into place last,
+    /// with 3 of the 4 corners around its center taken, mini unless both in front are
+    fn spin(&self) -> Spin {
+        let block = self.current_block();
+        if block.kind() != block::T_KIND || !self.rotated {
+            return Spin::None;
+        }
+
+        // the center is next to the other 3, the one in front has nothing behind
+        let points = block.points();
+        let center = Point::new(
+            block.placement().offset.x + 1,
+            block.placement().offset.y + 1,
+        );
+        let front = points
+            .iter()
+            .find(|p| {
+                **p != center
+                    && !points.contains(&Point::new(2 * center.x - p.x, 2 * center.y - p.y))
+            })
+            .unwrap();
+        let (dx, dy) = (front.x - center.x, front.y - center.y);
+
+        let taken =
+            |x: isize, y: isize| !self.stacked.fits(&[Point::new(center.x + x, center.y + y)]);
+        let corners = [(1, 1), (1, -1), (-1, 1), (-1, -1)]
+            .iter()
+            .filter(|(x, y)| taken(*x, *y))
+            .count();
+        let in_front = taken(dx - dy, dy + dx) as usize + taken(dx + dy, dy - dx) as usize;
+
+        match (corners, in_front) {
+            (0..=2, _) => Spin::None,
+            (_, 2) => Spin::Full,
+            _ => Spin::Mini,
+        }
+    }
+
+    /// garbage `clear` sends cancels incoming garbage first, oldest first
+    fn attack(&mut self, clear: &Clear) {
+        let mut attack = self.garbage.attack(clear);
+        self.record.attack += attack;
+
+        let pending = self.pending_garbage();
+        while attack > 0 {
+            let front = match self.incoming.front_mut() {
+                Some(front) => front,
+                None => break,
+            };
+            let cancel = attack.min(*front);
+            attack -= cancel;
+            *front -= cancel;
+            if *front == 0 {
+                self.incoming.pop_front();
+            }
+        }
+
+        if self.pending_garbage() != pending {
+            self.changes.push(Change::Pending(self.pending_garbage()));
+        }
+        if attack > 0 {
+            self.changes.push(Change::Sent(attack));
+        }
+    }
+
+    /// incoming garbage rises: a new hole for each attack, moved from line to line
+    /// by the messiness, false if it tops out
+    fn rise(&mut self) -> bool {
+        let column = self.rules.size.0;
+        let mut lines = 0;
+        let mut fits = true;
+        while let Some(count) = self.incoming.pop_front() {
+            let mut hole = self.garbage_rng.gen_range(0..column);
+            for i in 0..count {
+                if i > 0 && self.garbage_rng.gen_bool(self.garbage.messiness) {
+                    // any other column
+                    hole = (hole + self.garbage_rng.gen_range(1..column)) % column;
+                }
+                fits &= self.stacked.raise(1, hole);
+            }
+            lines += count;
+        }
+        self.changes.push(Change::Pending(0));
+        self.changes.push(Change::Risen(lines));
+
+        if !fits {
+            self.over = true;
+            self.changes.push(Change::Over);
+        }
+        fits
+    }
+
+    /// returns the block replaced by the next one
+    fn generate_new_block(&mut self) -> Box<FallingBlock> {
+        let block = FallingBlock::random(&mut self.rng);
+        let block = self.blocks.push(Box::new(block));
+
+        self.changes.push(Change::Spawned);
+        self.spawn();
+        block
+    }
+
+    /// move current block to the top of game frame
+    fn spawn(&mut self) {
+        let origin = self.spawn_point();
+        self.blocks.current_block_mut().shift(&origin);
+        self.rotated = false;
+        self.changes
+            .push(Change::Entered(self.current_block().placement()));
+
+        if !self.is_valid_position(self.current_block().points()) {
+            self.over = true;
+            self.changes.push(Change::Over);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// index of the I block in BLOCKS
+    const I_KIND: usize = 4;
+
+    /// a new game with its four bottom lines filled but the column on the right,
+    /// and an upright i block coming in over them
+    fn four_lines_open(seed: u64) -> Engine {
+        let mut engine = Engine::new(Rules::new(seed));
+        let (column, _) = engine.rules.size;
+        for y in 0..4 {
+            for x in 0..column - 1 {
+                let color = *block::BLOCKS[0].color();
+                engine.stacked.cover(color, &[Point::new(x as isize, y)]);
+            }
+        }
+        *engine.blocks.current_block_mut() = FallingBlock::new(I_KIND, 1);
+        engine.spawn();
+        engine.take_changes();
+        engine
+    }
+
+    /// move the i block to the right wall and hard drop it, filling the four lines
+    fn fill_four_lines(engine: &mut Engine) {
+        while engine.apply(Event::Right) && engine.take_changes().contains(&Change::Moved) {}
+        engine.apply(Event::HardDrop);
+    }
+
+    #[test]
+    fn hard_drop_locks() {
+        let mut engine = Engine::new(Rules::new(42));
+        let next = engine.next_block().kind();
+
+        assert!(engine.apply(Event::HardDrop));
+        assert_eq!(engine.pieces(), 1);
+        assert_eq!(engine.current_block().kind(), next);
+        assert!(engine.stacked().lines().iter().any(|line| *line != 0));
+
+        let changes = engine.take_changes();
+        assert!(changes.iter().any(|c| matches!(c, Change::Locked(_))));
+        assert!(changes.contains(&Change::Spawned));
+    }
+
+    #[test]
+    fn gravity_locks() {
+        let rules = Rules::new(42);
+        let mut engine = Engine::new(rules);
+
+        // 20 lines to fall, one more drop to lock
+        engine.tick(rules.gravity_interval() * 21);
+        assert_eq!(engine.pieces(), 1);
+        assert_eq!(engine.record().keys, 0);
+    }
+
+    #[test]
+    fn full_lines_clear_after_the_delay() {
+        let mut engine = four_lines_open(42);
+        fill_four_lines(&mut engine);
+        assert_eq!(engine.pieces(), 1);
+
+        let (lines, _) = engine.clearing().unwrap();
+        assert_eq!(lines, [0, 1, 2, 3]);
+        assert!(!engine.apply(Event::Left));
+        assert!(engine
+            .take_changes()
+            .contains(&Change::Full(vec![0, 1, 2, 3])));
+
+        engine.tick(engine.rules().clear_delay);
+        assert!(engine.clearing().is_none());
+        assert_eq!(engine.record().line, 4);
+        assert!(engine.stacked().lines().iter().all(|line| *line == 0));
+        assert!(engine.take_changes().contains(&Change::Cleared(4)));
+    }
+
+    #[test]
+    fn clears_cancel_oldest_garbage_first() {
+        let mut engine = four_lines_open(42);
+        engine.receive(2);
+        engine.receive(15);
+        engine.take_changes();
+        fill_four_lines(&mut engine);
+
+        // a perfect tetris sends 4 + 10 lines
+        assert_eq!(engine.record().attack, 14);
+        assert_eq!(engine.incoming, [3]);
+
+        let changes = engine.take_changes();
+        assert!(changes.contains(&Change::Pending(3)));
+        assert!(!changes.iter().any(|c| matches!(c, Change::Sent(_))));
+    }
+
+    #[test]
+    fn clears_send_what_garbage_does_not_take() {
+        let mut engine = four_lines_open(42);
+        engine.receive(4);
+        fill_four_lines(&mut engine);
+
+        assert_eq!(engine.pending_garbage(), 0);
+        assert!(engine.take_changes().contains(&Change::Sent(10)));
+    }
+
+    #[test]
+    fn garbage_rises_on_a_lock_without_clear() {
+        let mut engine = Engine::new(Rules::new(42));
+        engine.receive(3);
+        engine.apply(Event::HardDrop);
+
+        assert_eq!(engine.pending_garbage(), 0);
+        let lines = engine.stacked().lines();
+        assert!(lines[..3].iter().all(|line| line.count_ones() == 9));
+        assert!(engine.take_changes().contains(&Change::Risen(3)));
+    }
+
+    #[test]
+    fn hard_drops_top_out() {
+        let mut engine = Engine::new(Rules::new(42));
+        while !engine.is_over() {
+            assert!(engine.apply(Event::HardDrop));
+            engine.tick(Duration::from_millis(100));
+        }
+
+        assert!(engine.take_changes().contains(&Change::Over));
+        assert!(!engine.apply(Event::HardDrop));
+        let pieces = engine.pieces();
+        engine.tick(Duration::from_secs(60));
+        assert_eq!(engine.pieces(), pieces);
+    }
+}
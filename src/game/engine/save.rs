@@ -0,0 +1,525 @@
+use super::{Engine, Record, Rules, TwoBlocks};
+use crate::game::block::{
+    FallingBlock, Point, StackedBlock, BLOCKS, GARBAGE_COLOR, MAX_COLUMN, MAX_ROW,
+    ORIENTATION_COUNT, POINT_OF_BLOCK_COUNT,
+};
+use crate::game::garbage::Garbage;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use terminal::Color;
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::time::Duration;
+
+const SAVE_VERSION: u32 = 1;
+
+const EMPTY_CELL: char = '.';
+const GARBAGE_CELL: char = 'G';
+
+/// a game in the middle, written as text
+///
+/// ~~~text
+/// tetris-save 1
+/// seed 42
+/// size 10 20
+/// speed 1
+/// drop-interval 2000
+/// clear-delay 1600
+/// rng <32 bytes in hex> <stream> <word position>
+/// garbage-rng <32 bytes in hex> <stream> <word position>
+/// incoming 3 1
+/// current 5 0 3 17
+/// next 1 2
+/// hold 4 1 held
+/// record 61 4 10 1 23 95 2
+/// clock 61250
+/// gravity 830
+/// clearing none
+/// streak 2 1 0
+/// over 0
+/// board
+/// ..........
+/// 3311....66
+/// ~~~
+///
+/// record is `<seconds> <lines> <score> <speed> <pieces> <keys> <attack>`,
+/// blocks are `<index in BLOCKS> <orientation>`, the current one with its offset,
+/// board rows go from top to bottom, a cell is `.`, `G` for garbage or the index of its block,
+/// `incoming` is `none` or the garbage attacks waiting to rise, oldest first,
+/// `clearing` is `none` or the time full lines are shown followed by the lines,
+/// `streak` is `<combo> <back-to-back> <rotated>`, `over` is 1 once the blocks stacked out of the top
+impl Engine {
+    pub fn save(&self) -> String {
+        let mut s = String::new();
+        self.write_save(&mut s).unwrap();
+        s
+    }
+
+    fn write_save(&self, s: &mut String) -> std::fmt::Result {
+        let Rules {
+            seed,
+            size: (column, row),
+            speed,
+            drop_interval,
+            clear_delay,
+        } = self.rules;
+        writeln!(s, "tetris-save {}", SAVE_VERSION)?;
+        writeln!(s, "seed {}", seed)?;
+        writeln!(s, "size {} {}", column, row)?;
+        writeln!(s, "speed {}", speed)?;
+        writeln!(s, "drop-interval {}", drop_interval.as_millis())?;
+        writeln!(s, "clear-delay {}", clear_delay.as_millis())?;
+
+        write_rng(s, "rng", &self.rng)?;
+        write_rng(s, "garbage-rng", &self.garbage_rng)?;
+        write!(s, "incoming")?;
+        if self.incoming.is_empty() {
+            write!(s, " none")?;
+        }
+        for lines in &self.incoming {
+            write!(s, " {}", lines)?;
+        }
+        writeln!(s)?;
+
+        let curr = self.blocks.current_block();
+        let offset = curr.offset();
+        writeln!(
+            s,
+            "current {} {} {} {}",
+            curr.kind(),
+            curr.orientation(),
+            offset.x,
+            offset.y
+        )?;
+        let next = self.blocks.next_block();
+        writeln!(s, "next {} {}", next.kind(), next.orientation())?;
+        match self.blocks.hold_block() {
+            Some(hold) => write!(s, "hold {} {}", hold.kind(), hold.orientation())?,
+            None => write!(s, "hold none")?,
+        }
+        writeln!(s, "{}", if self.blocks.can_hold() { "" } else { " held" })?;
+
+        let Record {
+            secs,
+            line,
+            score,
+            speed,
+            pieces,
+            keys,
+            attack,
+        } = self.record;
+        writeln!(
+            s,
+            "record {} {} {} {} {} {} {}",
+            secs, line, score, speed, pieces, keys, attack
+        )?;
+        writeln!(s, "clock {}", self.clock.as_millis())?;
+        writeln!(s, "gravity {}", self.gravity.as_millis())?;
+        match &self.clearing {
+            Some((lines, shown)) => {
+                write!(s, "clearing {}", shown.as_millis())?;
+                for line in lines {
+                    write!(s, " {}", line)?;
+                }
+                writeln!(s)?;
+            }
+            None => writeln!(s, "clearing none")?,
+        }
+        writeln!(
+            s,
+            "streak {} {} {}",
+            self.combo, self.back_to_back as u8, self.rotated as u8
+        )?;
+        writeln!(s, "over {}", self.over as u8)?;
+
+        writeln!(s, "board")?;
+        for line in self.stacked.colors().iter().rev() {
+            let line: String = line.iter().map(|c| color_to_cell(*c)).collect();
+            writeln!(s, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// the game written by `save`, it goes on exactly as it would have
+    pub fn restore(s: &str) -> Result<Self, String> {
+        let mut lines = s.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+        let mut field = |name: &str| -> Result<(usize, Vec<&str>), String> {
+            let (i, line) = lines.next().ok_or_else(|| format!("missing `{}`", name))?;
+            let mut words = line.split_whitespace();
+            if words.next() != Some(name) {
+                return Err(format!("line {}: expect `{}`", i + 1, name));
+            }
+            Ok((i + 1, words.collect()))
+        };
+        let number = |i: usize, w: &str| -> Result<u64, String> {
+            w.parse()
+                .map_err(|_| format!("line {}: bad number `{}`", i, w))
+        };
+        let numbers = |(i, words): (usize, Vec<&str>), n: usize| -> Result<Vec<u64>, String> {
+            if words.len() != n {
+                return Err(format!("line {}: expect {} number(s)", i, n));
+            }
+            words.iter().map(|w| number(i, w)).collect()
+        };
+        let small = |(i, words): (usize, Vec<&str>), n: usize| -> Result<Vec<u32>, String> {
+            if words.len() != n {
+                return Err(format!("line {}: expect {} number(s)", i, n));
+            }
+            words
+                .iter()
+                .map(|w| {
+                    w.parse()
+                        .map_err(|_| format!("line {}: bad number `{}`", i, w))
+                })
+                .collect()
+        };
+
+        let version = numbers(field("tetris-save")?, 1)?[0];
+        if version != SAVE_VERSION as u64 {
+            return Err(format!("unsupported save version {}", version));
+        }
+
+        let seed = numbers(field("seed")?, 1)?[0];
+        let size = small(field("size")?, 2)?;
+        let (column, row) = (size[0] as usize, size[1] as usize);
+        let speed = small(field("speed")?, 1)?[0];
+        // no drop interval is no gravity, as the trainer plays
+        let drop_interval = numbers(field("drop-interval")?, 1)?[0];
+        let clear_delay = numbers(field("clear-delay")?, 1)?[0];
+        if !(POINT_OF_BLOCK_COUNT..=MAX_COLUMN).contains(&column)
+            || !(1..=MAX_ROW).contains(&row)
+            || speed == 0
+        {
+            return Err(String::from("bad save rules"));
+        }
+        let rules = Rules {
+            seed,
+            size: (column, row),
+            speed,
+            drop_interval: Duration::from_millis(drop_interval),
+            clear_delay: Duration::from_millis(clear_delay),
+        };
+
+        let rng = parse_rng(field("rng")?)?;
+        let garbage_rng = parse_rng(field("garbage-rng")?)?;
+        let (i, words) = field("incoming")?;
+        let incoming = match words[..] {
+            ["none"] => VecDeque::new(),
+            [] => {
+                return Err(format!(
+                    "line {}: expect `incoming none` or `incoming <lines>`",
+                    i
+                ))
+            }
+            _ => words
+                .iter()
+                .map(|w| match number(i, w).map(u32::try_from) {
+                    Ok(Ok(lines)) if lines > 0 => Ok(lines),
+                    _ => Err(format!("line {}: bad attack `{}`", i, w)),
+                })
+                .collect::<Result<_, _>>()?,
+        };
+
+        let block = |i: usize, kind: &str, orientation: &str| -> Result<FallingBlock, String> {
+            let (kind, orientation) = (number(i, kind)? as usize, number(i, orientation)? as usize);
+            if kind >= BLOCKS.len() || orientation >= ORIENTATION_COUNT {
+                return Err(format!("line {}: no such block", i));
+            }
+            Ok(FallingBlock::new(kind, orientation))
+        };
+
+        let (i, words) = field("current")?;
+        let curr = match words[..] {
+            [kind, orientation, x, y] => {
+                let mut curr = block(i, kind, orientation)?;
+                let offset = |w: &str| -> Result<isize, String> {
+                    w.parse()
+                        .map_err(|_| format!("line {}: bad number `{}`", i, w))
+                };
+                curr.shift(&Point::new(offset(x)?, offset(y)?));
+                curr
+            }
+            _ => {
+                return Err(format!(
+                    "line {}: expect `current <block> <orientation> <x> <y>`",
+                    i
+                ))
+            }
+        };
+        let (i, words) = field("next")?;
+        let next = match words[..] {
+            [kind, orientation] => block(i, kind, orientation)?,
+            _ => return Err(format!("line {}: expect `next <block> <orientation>`", i)),
+        };
+
+        let mut blocks = TwoBlocks::new(Box::new(curr), Box::new(next));
+        let (i, words) = field("hold")?;
+        let held = match words[..] {
+            ["none"] => false,
+            ["none", "held"] => true,
+            [kind, orientation] => {
+                blocks.set_hold(Box::new(block(i, kind, orientation)?));
+                false
+            }
+            [kind, orientation, "held"] => {
+                blocks.set_hold(Box::new(block(i, kind, orientation)?));
+                true
+            }
+            _ => {
+                return Err(format!(
+                    "line {}: expect `hold <block> <orientation>` or `hold none`",
+                    i
+                ))
+            }
+        };
+        blocks.held = held;
+
+        let record = small(field("record")?, 7)?;
+        let record = Record {
+            secs: record[0],
+            line: record[1],
+            score: record[2],
+            speed: record[3],
+            pieces: record[4],
+            keys: record[5],
+            attack: record[6],
+        };
+        let clock = Duration::from_millis(numbers(field("clock")?, 1)?[0]);
+        let gravity = Duration::from_millis(numbers(field("gravity")?, 1)?[0]);
+
+        let (i, words) = field("clearing")?;
+        let clearing = match words[..] {
+            ["none"] => None,
+            [shown, ref lines @ ..] if !lines.is_empty() => {
+                let shown = Duration::from_millis(number(i, shown)?);
+                let lines = lines
+                    .iter()
+                    .map(|w| number(i, w).map(|n| n as usize))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Some((lines, shown))
+            }
+            _ => {
+                return Err(format!(
+                    "line {}: expect `clearing none` or `clearing <time> <lines>`",
+                    i
+                ))
+            }
+        };
+
+        let streak = small(field("streak")?, 3)?;
+        let (combo, back_to_back, rotated) = (streak[0], streak[1] != 0, streak[2] != 0);
+        let over = small(field("over")?, 1)?[0] != 0;
+
+        field("board")?;
+        let mut stacked = StackedBlock::new(column, row);
+        for y in (0..row).rev() {
+            let (i, line) = lines.next().ok_or("board is too short")?;
+            let line = line.trim();
+            if line.chars().count() != column {
+                return Err(format!("line {}: expect {} cell(s)", i + 1, column));
+            }
+            for (x, cell) in line.chars().enumerate() {
+                let color = cell_to_color(cell)
+                    .ok_or_else(|| format!("line {}: bad cell `{}`", i + 1, cell))?;
+                stacked.cover(color, &[Point::new(x as isize, y as isize)]);
+            }
+        }
+        if let Some((i, _)) = lines.next() {
+            return Err(format!("line {}: board is too long", i + 1));
+        }
+
+        if clearing
+            .as_ref()
+            .is_some_and(|(lines, _)| *lines != stacked.full_lines())
+        {
+            return Err(String::from("clearing lines are not full"));
+        }
+
+        let engine = Engine {
+            rules,
+            rng,
+            garbage_rng,
+            garbage: Garbage::default(),
+            incoming,
+            combo,
+            back_to_back,
+            rotated,
+            blocks,
+            stacked,
+            record,
+            clock,
+            gravity,
+            clearing,
+            over,
+            changes: Vec::new(),
+        };
+        // a game that is over ended with current block where it can't be
+        if !engine.over
+            && engine.clearing.is_none()
+            && !engine.is_valid_position(engine.current_block().points())
+        {
+            return Err(String::from("current block is out of place"));
+        }
+        Ok(engine)
+    }
+}
+
+/// `<name> <32 bytes in hex> <stream> <word position>`
+fn write_rng(s: &mut String, name: &str, rng: &ChaCha8Rng) -> std::fmt::Result {
+    let seed: String = rng
+        .get_seed()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    writeln!(
+        s,
+        "{} {} {} {}",
+        name,
+        seed,
+        rng.get_stream(),
+        rng.get_word_pos()
+    )
+}
+
+fn parse_rng((i, words): (usize, Vec<&str>)) -> Result<ChaCha8Rng, String> {
+    let bad = || format!("line {}: bad rng", i);
+    let (seed, stream, word_pos) = match words[..] {
+        [seed, stream, word_pos] => (seed, stream, word_pos),
+        _ => return Err(bad()),
+    };
+    if seed.len() != 64 || !seed.is_ascii() {
+        return Err(bad());
+    }
+    let mut bytes = [0u8; 32];
+    for (j, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&seed[2 * j..2 * j + 2], 16).map_err(|_| bad())?;
+    }
+    let mut rng = ChaCha8Rng::from_seed(bytes);
+    rng.set_stream(stream.parse().map_err(|_| bad())?);
+    rng.set_word_pos(word_pos.parse().map_err(|_| bad())?);
+    Ok(rng)
+}
+
+fn color_to_cell(color: Color) -> char {
+    if color == GARBAGE_COLOR {
+        return GARBAGE_CELL;
+    }
+    BLOCKS
+        .iter()
+        .position(|b| *b.color() == color)
+        .map_or(EMPTY_CELL, |i| char::from(b'0' + i as u8))
+}
+
+fn cell_to_color(cell: char) -> Option<Color> {
+    if cell == EMPTY_CELL {
+        return Some(Color::Reset);
+    }
+    if cell == GARBAGE_CELL {
+        return Some(GARBAGE_COLOR);
+    }
+    let i = cell.to_digit(10)? as usize;
+    BLOCKS.get(i).map(|b| *b.color())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::bot::{Bot, Weights};
+    use crate::game::event::Event;
+
+    /// the save of a new game of `seed` with `name` written as `value` instead
+    fn saved(seed: u64, name: &str, value: &str) -> String {
+        Engine::new(Rules::new(seed))
+            .save()
+            .lines()
+            .map(|line| match line.split_whitespace().next() {
+                Some(word) if word == name => format!("{} {}\n", name, value),
+                _ => format!("{}\n", line),
+            })
+            .collect()
+    }
+
+    /// the bot places `pieces` blocks, a gravity drop between its inputs
+    fn play(engine: &mut Engine, pieces: u32) {
+        let bot = Bot::new(Weights::default());
+        let target = engine.pieces() + pieces;
+        while !engine.is_over() && engine.pieces() < target {
+            for event in bot.think(engine) {
+                engine.apply(event);
+                engine.tick(Duration::from_millis(700));
+            }
+            engine.tick(engine.rules().clear_delay);
+        }
+    }
+
+    #[test]
+    fn round_trip_mid_game() {
+        let mut engine = Engine::new(Rules::new(42));
+        play(&mut engine, 20);
+        engine.receive(2);
+        engine.receive(3);
+
+        let save = engine.save();
+        assert!(save.contains("\nincoming 2 3\n"));
+        let mut restored = Engine::restore(&save).unwrap();
+        assert_eq!(restored.save(), save);
+
+        // the same game goes on from there, garbage rising the same way
+        play(&mut engine, 20);
+        play(&mut restored, 20);
+        assert!(engine.record().line > 0);
+        assert_eq!(restored.save(), engine.save());
+    }
+
+    #[test]
+    fn round_trip_at_top_out() {
+        let mut engine = Engine::new(Rules::new(42));
+        while !engine.is_over() {
+            engine.apply(Event::HardDrop);
+            engine.tick(Duration::from_secs(2));
+        }
+
+        let save = engine.save();
+        let restored = Engine::restore(&save).unwrap();
+        assert!(restored.is_over());
+        assert_eq!(restored.save(), save);
+    }
+
+    #[test]
+    fn no_gravity_is_restored() {
+        let rules = Rules {
+            drop_interval: Duration::ZERO,
+            ..Rules::new(42)
+        };
+        let save = Engine::new(rules).save();
+        let restored = Engine::restore(&save).unwrap();
+        assert!(restored.rules().drop_interval.is_zero());
+        assert_eq!(restored.save(), save);
+    }
+
+    #[test]
+    fn sizes_and_counts_are_checked() {
+        for (name, value) in [
+            ("size", "40 20"),
+            ("size", "10 0"),
+            ("size", "10 18446744073709551615"),
+            ("size", "10 1001"),
+            ("speed", "4294967296"),
+            ("record", "0 0 4294967296 1 0 0 0"),
+            ("streak", "4294967296 0 0"),
+        ] {
+            assert!(
+                Engine::restore(&saved(42, name, value)).is_err(),
+                "{} {}",
+                name,
+                value
+            );
+        }
+
+        let tall = saved(42, "size", "10 1000");
+        let board = "..........\n".repeat(980);
+        assert!(Engine::restore(&format!("{}{}", tall, board)).is_ok());
+    }
+}
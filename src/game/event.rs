@@ -1,49 +1,473 @@
+use std::fmt;
+use std::str::FromStr;
+
 use terminal::{
     self, Event as Tevent, KeyCode as Code, KeyEvent as Kevent,
     KeyModifiers::{self as Modifiers},
 };
 
 const EMPTY: Modifiers = Modifiers::empty();
-// const SHIFT: Modifiers = Modifiers::SHIFT;
+const SHIFT: Modifiers = Modifiers::SHIFT;
 const CONTROL: Modifiers = Modifiers::CONTROL;
-// const ALT: Modifiers = Modifiers::ALT;
+const ALT: Modifiers = Modifiers::ALT;
 
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Event {
     Unknow,
     Quit,
     Toggle, // Pause or Resume
     ClockRotate,
     InverseRotate,
+    FlipRotate, // rotate 180 degrees
+    Hold,
     Drop,
     Left,
     Right,
     HardDrop,
+    Hint, // show or hide where the bot would place current block
     Resize,
+    Gravity, // drop by time
 }
 
-impl From<Tevent> for Event {
-    fn from(value: terminal::Event) -> Self {
-        match value {
-            Tevent::Key(key) => {
-                let Kevent {
-                    code: c,
-                    modifiers: m,
-                } = key;
-
-                match (c, m) {
-                    (Code::Char('c'), CONTROL) | (Code::Char('q'), EMPTY) => Event::Quit,
-                    (Code::Char('p'), EMPTY) => Event::Toggle,
-                    (Code::Up | Code::Char('z'), EMPTY) => Event::ClockRotate,
-                    (Code::Char('x'), EMPTY) => Event::InverseRotate,
-                    (Code::Down, EMPTY) => Event::Drop,
-                    (Code::Left, EMPTY) => Event::Left,
-                    (Code::Right, EMPTY) => Event::Right,
-                    (Code::Char(' '), EMPTY) => Event::HardDrop,
-                    _ => Event::Unknow,
+/// events that can be bound to keys, in the order they are listed
+pub const ACTIONS: [Event; 11] = [
+    Event::Left,
+    Event::Right,
+    Event::Drop,
+    Event::HardDrop,
+    Event::ClockRotate,
+    Event::InverseRotate,
+    Event::FlipRotate,
+    Event::Hold,
+    Event::Hint,
+    Event::Toggle,
+    Event::Quit,
+];
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Unknow => "unknow",
+            Event::Quit => "quit",
+            Event::Toggle => "toggle",
+            Event::ClockRotate => "clock_rotate",
+            Event::InverseRotate => "inverse_rotate",
+            Event::FlipRotate => "flip_rotate",
+            Event::Hold => "hold",
+            Event::Hint => "hint",
+            Event::Drop => "drop",
+            Event::Left => "left",
+            Event::Right => "right",
+            Event::HardDrop => "hard_drop",
+            Event::Resize => "resize",
+            Event::Gravity => "gravity",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ACTIONS.iter().find(|e| e.name() == name).copied()
+    }
+
+    /// a game could not be left or paused without a key for these
+    pub fn needs_key(&self) -> bool {
+        matches!(self, Event::Quit | Event::Toggle)
+    }
+}
+
+/// a key code together with its modifiers
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Key {
+    pub code: Code,
+    pub modifiers: Modifiers,
+}
+
+impl Key {
+    /// letters carry shift in their case, other characters ignore shift
+    pub fn new(code: Code, modifiers: Modifiers) -> Self {
+        match code {
+            Code::Char(c) if c.is_ascii_alphabetic() => {
+                if c.is_ascii_uppercase() || modifiers.contains(SHIFT) {
+                    Key {
+                        code: Code::Char(c.to_ascii_uppercase()),
+                        modifiers: modifiers | SHIFT,
+                    }
+                } else {
+                    Key { code, modifiers }
                 }
             }
+            Code::Char(_) => Key {
+                code,
+                modifiers: modifiers - SHIFT,
+            },
+            _ => Key { code, modifiers },
+        }
+    }
+
+    const fn plain(code: Code) -> Self {
+        Key {
+            code,
+            modifiers: EMPTY,
+        }
+    }
+
+    const fn char(c: char) -> Self {
+        Key {
+            code: Code::Char(c),
+            modifiers: if c.is_ascii_uppercase() { SHIFT } else { EMPTY },
+        }
+    }
+}
+
+impl From<Kevent> for Key {
+    fn from(value: Kevent) -> Self {
+        Key::new(value.code, value.modifiers)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(ALT) {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.contains(SHIFT) && !matches!(self.code, Code::Char(_)) {
+            write!(f, "shift+")?;
+        }
+
+        match self.code {
+            Code::Char(' ') => write!(f, "space"),
+            Code::Char(',') => write!(f, "comma"),
+            Code::Char('#') => write!(f, "hash"),
+            Code::Char(c) => write!(f, "{}", c),
+            Code::F(n) => write!(f, "f{}", n),
+            Code::Backspace => write!(f, "backspace"),
+            Code::Enter => write!(f, "enter"),
+            Code::Left => write!(f, "left"),
+            Code::Right => write!(f, "right"),
+            Code::Up => write!(f, "up"),
+            Code::Down => write!(f, "down"),
+            Code::Home => write!(f, "home"),
+            Code::End => write!(f, "end"),
+            Code::PageUp => write!(f, "pageup"),
+            Code::PageDown => write!(f, "pagedown"),
+            Code::Tab => write!(f, "tab"),
+            Code::BackTab => write!(f, "backtab"),
+            Code::Delete => write!(f, "delete"),
+            Code::Insert => write!(f, "insert"),
+            Code::Null => write!(f, "null"),
+            Code::Esc => write!(f, "esc"),
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = String;
+
+    /// e.g. `z`, `Z`, `shift+z`, `ctrl+c`, `space`, `left`, `f1`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = EMPTY;
+        let mut rest = s.trim();
+
+        while let Some((m, r)) = rest.split_once('+').filter(|(_, r)| !r.is_empty()) {
+            modifiers |= match m.to_ascii_lowercase().as_str() {
+                "shift" => SHIFT,
+                "ctrl" | "control" => CONTROL,
+                "alt" => ALT,
+                _ => return Err(format!("unknown modifier `{}` in key `{}`", m, s)),
+            };
+            rest = r;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => Code::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "space" => Code::Char(' '),
+                "comma" => Code::Char(','),
+                "hash" => Code::Char('#'),
+                "backspace" => Code::Backspace,
+                "enter" => Code::Enter,
+                "left" => Code::Left,
+                "right" => Code::Right,
+                "up" => Code::Up,
+                "down" => Code::Down,
+                "home" => Code::Home,
+                "end" => Code::End,
+                "pageup" => Code::PageUp,
+                "pagedown" => Code::PageDown,
+                "tab" => Code::Tab,
+                "backtab" => Code::BackTab,
+                "delete" => Code::Delete,
+                "insert" => Code::Insert,
+                "esc" => Code::Esc,
+                f => match f.strip_prefix('f').and_then(|n| n.parse().ok()) {
+                    Some(n) => Code::F(n),
+                    None => return Err(format!("unknown key `{}`", s)),
+                },
+            },
+        };
+
+        Ok(Key::new(code, modifiers))
+    }
+}
+
+/// bindings from keys to events, an event may have several keys
+#[derive(Clone)]
+pub struct KeyMap {
+    bindings: Vec<(Event, Vec<Key>)>,
+}
+
+pub const PRESETS: [&str; 4] = ["default", "vim", "wasd", "guideline"];
+
+const CTRL_C: Key = Key {
+    code: Code::Char('c'),
+    modifiers: CONTROL,
+};
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::from_slice(&[
+            (Event::Left, &[Key::plain(Code::Left)]),
+            (Event::Right, &[Key::plain(Code::Right)]),
+            (Event::Drop, &[Key::plain(Code::Down)]),
+            (Event::HardDrop, &[Key::char(' ')]),
+            (Event::ClockRotate, &[Key::plain(Code::Up), Key::char('z')]),
+            (Event::InverseRotate, &[Key::char('x')]),
+            (Event::FlipRotate, &[Key::char('a')]),
+            (Event::Hold, &[Key::char('c')]),
+            (Event::Hint, &[Key::char('i')]),
+            (Event::Toggle, &[Key::char('p')]),
+            (Event::Quit, &[Key::char('q'), CTRL_C]),
+        ])
+    }
+}
+
+impl KeyMap {
+    fn from_slice(bindings: &[(Event, &[Key])]) -> Self {
+        KeyMap {
+            bindings: bindings
+                .iter()
+                .map(|(event, keys)| (*event, keys.to_vec()))
+                .collect(),
+        }
+    }
+
+    pub fn vim() -> Self {
+        Self::from_slice(&[
+            (Event::Left, &[Key::char('h')]),
+            (Event::Right, &[Key::char('l')]),
+            (Event::Drop, &[Key::char('j')]),
+            (Event::HardDrop, &[Key::char(' ')]),
+            (Event::ClockRotate, &[Key::char('k')]),
+            (Event::InverseRotate, &[Key::char('u')]),
+            (Event::FlipRotate, &[Key::char('y')]),
+            (Event::Hold, &[Key::char('f')]),
+            (Event::Hint, &[Key::char('i')]),
+            (Event::Toggle, &[Key::char('p')]),
+            (Event::Quit, &[Key::char('q'), CTRL_C]),
+        ])
+    }
+
+    pub fn wasd() -> Self {
+        Self::from_slice(&[
+            (Event::Left, &[Key::char('a')]),
+            (Event::Right, &[Key::char('d')]),
+            (Event::Drop, &[Key::char('s')]),
+            (Event::HardDrop, &[Key::char(' ')]),
+            (Event::ClockRotate, &[Key::char('w'), Key::char('e')]),
+            (Event::InverseRotate, &[Key::char('q')]),
+            (Event::FlipRotate, &[Key::char('r')]),
+            (Event::Hold, &[Key::char('f')]),
+            (Event::Hint, &[Key::char('i')]),
+            (Event::Toggle, &[Key::char('p')]),
+            (Event::Quit, &[Key::plain(Code::Esc), CTRL_C]),
+        ])
+    }
+
+    pub fn guideline() -> Self {
+        Self::from_slice(&[
+            (Event::Left, &[Key::plain(Code::Left)]),
+            (Event::Right, &[Key::plain(Code::Right)]),
+            (Event::Drop, &[Key::plain(Code::Down)]),
+            (Event::HardDrop, &[Key::char(' ')]),
+            (Event::ClockRotate, &[Key::plain(Code::Up), Key::char('x')]),
+            (Event::InverseRotate, &[Key::char('z')]),
+            (Event::FlipRotate, &[Key::char('a')]),
+            (Event::Hold, &[Key::char('c'), Key::char('C')]),
+            (Event::Hint, &[Key::char('i')]),
+            (Event::Toggle, &[Key::char('p'), Key::plain(Code::F(1))]),
+            (Event::Quit, &[Key::char('q'), CTRL_C]),
+        ])
+    }
+
+    /// keys of the left player of a versus game, who also pauses and quits
+    pub fn versus_left() -> Self {
+        Self::from_slice(&[
+            (Event::Left, &[Key::char('a')]),
+            (Event::Right, &[Key::char('d')]),
+            (Event::Drop, &[Key::char('s')]),
+            (Event::HardDrop, &[Key::char(' ')]),
+            (Event::ClockRotate, &[Key::char('w')]),
+            (Event::InverseRotate, &[Key::char('q')]),
+            (Event::FlipRotate, &[Key::char('e')]),
+            (Event::Hold, &[Key::char('c')]),
+            (Event::Toggle, &[Key::char('p')]),
+            (Event::Quit, &[Key::plain(Code::Esc), CTRL_C]),
+        ])
+    }
+
+    /// keys of the right player of a versus game, none of them taken by the left one
+    pub fn versus_right() -> Self {
+        Self::from_slice(&[
+            (Event::Left, &[Key::plain(Code::Left)]),
+            (Event::Right, &[Key::plain(Code::Right)]),
+            (Event::Drop, &[Key::plain(Code::Down)]),
+            (Event::HardDrop, &[Key::plain(Code::Enter)]),
+            (Event::ClockRotate, &[Key::plain(Code::Up)]),
+            (Event::InverseRotate, &[Key::char('/')]),
+            (Event::FlipRotate, &[Key::char('.')]),
+            (Event::Hold, &[Key::char(',')]),
+        ])
+    }
+
+    /// no keys at all, for a board the bot plays
+    pub fn none() -> Self {
+        Self::from_slice(&[])
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "vim" => Some(Self::vim()),
+            "wasd" => Some(Self::wasd()),
+            "guideline" => Some(Self::guideline()),
+            _ => None,
+        }
+    }
+
+    pub fn keys(&self, event: Event) -> &[Key] {
+        self.bindings
+            .iter()
+            .find(|(e, _)| *e == event)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+
+    /// keys bound to more than one event
+    pub fn conflicts(&self) -> Vec<Key> {
+        let mut keys: Vec<Key> = Vec::new();
+        for (i, (_, ks)) in self.bindings.iter().enumerate() {
+            for k in ks {
+                if !keys.contains(k) && self.bindings[i + 1..].iter().any(|(_, o)| o.contains(k)) {
+                    keys.push(*k);
+                }
+            }
+        }
+        keys
+    }
+
+    /// replace all keys of `event`, refused when it leaves quit or toggle without one
+    pub fn bind(&mut self, event: Event, keys: Vec<Key>) -> Result<(), String> {
+        if keys.is_empty() && event.needs_key() {
+            return Err(format!("{} keeps one key at least", event.name()));
+        }
+
+        if let Some((_, k)) = self.bindings.iter_mut().find(|(e, _)| *e == event) {
+            *k = keys;
+        } else {
+            self.bindings.push((event, keys));
+        }
+        Ok(())
+    }
+
+    pub fn lookup(&self, key: &Key) -> Event {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.contains(key))
+            .map_or(Event::Unknow, |(e, _)| *e)
+    }
+
+    pub fn get(&self, event: &Tevent) -> Event {
+        match event {
+            Tevent::Key(key) => self.lookup(&Key::from(*key)),
             Tevent::Resize => Event::Resize,
             _ => Event::Unknow,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_read_back_as_written() {
+        for s in [
+            "z",
+            "Z",
+            "ctrl+c",
+            "alt+x",
+            "shift+left",
+            "space",
+            "comma",
+            "hash",
+            "f1",
+            "esc",
+        ] {
+            let key: Key = s.parse().unwrap();
+            assert_eq!(key.to_string(), s);
+        }
+    }
+
+    #[test]
+    fn shift_goes_with_letters_only() {
+        let key = |s: &str| s.parse::<Key>().unwrap();
+        assert_eq!(key("shift+z"), key("Z"));
+        assert_eq!(key("SHIFT+z"), Key::new(Code::Char('z'), SHIFT));
+        assert_eq!(key("shift+/"), key("/"));
+        assert_eq!(key("+"), Key::char('+'));
+        assert_eq!(key("ctrl++"), Key::new(Code::Char('+'), CONTROL));
+    }
+
+    #[test]
+    fn unknown_keys_are_refused() {
+        assert!("hyper+x".parse::<Key>().is_err());
+        assert!("enterr".parse::<Key>().is_err());
+        assert!("fx".parse::<Key>().is_err());
+    }
+
+    #[test]
+    fn presets_have_no_conflicts() {
+        for name in PRESETS {
+            let keymap = KeyMap::preset(name).unwrap();
+            assert_eq!(keymap.conflicts(), [], "{}", name);
+            assert!(ACTIONS.iter().all(|e| !keymap.keys(*e).is_empty()));
+        }
+    }
+
+    #[test]
+    fn bind_replaces_the_keys_of_an_event() {
+        let mut keymap = KeyMap::default();
+        let k = Key::char('k');
+        keymap.bind(Event::Hold, vec![k]).unwrap();
+        assert_eq!(keymap.keys(Event::Hold), [k]);
+        assert_eq!(keymap.lookup(&k), Event::Hold);
+        assert_eq!(keymap.lookup(&Key::char('c')), Event::Unknow);
+
+        keymap.bind(Event::Left, vec![k]).unwrap();
+        assert_eq!(keymap.conflicts(), [k]);
+    }
+
+    #[test]
+    fn quit_and_toggle_keep_a_key() {
+        let mut keymap = KeyMap::default();
+        assert!(keymap.bind(Event::Quit, vec![]).is_err());
+        assert!(keymap.bind(Event::Toggle, vec![]).is_err());
+        assert_eq!(keymap.keys(Event::Toggle), [Key::char('p')]);
+
+        keymap.bind(Event::Quit, vec![CTRL_C]).unwrap();
+        keymap.bind(Event::Hint, vec![]).unwrap();
+        assert_eq!(keymap.keys(Event::Hint), []);
+    }
+}
@@ -0,0 +1,307 @@
+use super::block::{Placement, Point, PointArray};
+use super::engine::{Change, Engine};
+use super::event::Event;
+
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use std::collections::VecDeque;
+
+/// inputs that move a block before it drops, the ones finesse counts
+pub const MOVES: [Event; 5] = [
+    Event::ClockRotate,
+    Event::InverseRotate,
+    Event::FlipRotate,
+    Event::Left,
+    Event::Right,
+];
+
+/// points of a placement as it would land on the floor, sorted,
+/// equal for placements that cover the same cells after a drop
+fn footprint(placement: &Placement) -> PointArray {
+    let mut points = placement.points();
+    let bottom = points.iter().map(|p| p.y).min().unwrap();
+    for p in &mut points {
+        p.y -= bottom;
+    }
+    points.sort_by_key(|p| (p.x, p.y));
+    points
+}
+
+/// where `event` would move a placement to, if nothing is in the way
+pub fn moved(placement: &Placement, event: Event) -> Placement {
+    let mut placement = *placement;
+    match event {
+        Event::Left => placement.offset.x -= 1,
+        Event::Right => placement.offset.x += 1,
+        Event::Drop => placement.offset.y -= 1,
+        // the same turn as `FallingBlock += direction`
+        Event::ClockRotate => placement.orientation = (placement.orientation + 3) % 4,
+        Event::InverseRotate => placement.orientation = (placement.orientation + 1) % 4,
+        Event::FlipRotate => placement.orientation = (placement.orientation + 2) % 4,
+        _ => {}
+    }
+    placement
+}
+
+/// every placement `from` can be moved to where `valid` holds,
+/// one for each landing, with the fewest moves to get there
+pub fn search(from: Placement, valid: impl Fn(&Placement) -> bool) -> Vec<(Placement, Vec<Event>)> {
+    let mut seen = vec![from];
+    let mut queue = VecDeque::from([(from, Vec::new())]);
+    let mut found: Vec<(Placement, Vec<Event>)> = Vec::new();
+
+    while let Some((placement, moves)) = queue.pop_front() {
+        if found
+            .iter()
+            .all(|(p, _)| footprint(p) != footprint(&placement))
+        {
+            found.push((placement, moves.clone()));
+        }
+
+        for event in MOVES {
+            let next = moved(&placement, event);
+            if valid(&next) && !seen.contains(&next) {
+                seen.push(next);
+                let mut moves = moves.clone();
+                moves.push(event);
+                queue.push_back((next, moves));
+            }
+        }
+    }
+
+    found
+}
+
+/// every placement `from` can be moved to on an empty board `column` wide
+pub fn placements(from: Placement, column: usize) -> Vec<(Placement, Vec<Event>)> {
+    search(from, |p| {
+        p.points()
+            .iter()
+            .all(|Point { x, y }| 0 <= *x && *x < column as isize && 0 <= *y)
+    })
+}
+
+/// fewest moves from `from` to where `to` lands, on an empty board `column` wide
+pub fn best_moves(from: Placement, to: Placement, column: usize) -> Option<Vec<Event>> {
+    let target = footprint(&to);
+    placements(from, column)
+        .into_iter()
+        .find(|(p, _)| footprint(p) == target)
+        .map(|(_, moves)| moves)
+}
+
+/// a block placed, with the moves used and the fewest moves
+#[derive(Clone, Debug)]
+pub struct Placed {
+    pub placement: Placement,
+    pub used: Vec<Event>,
+    pub best: Vec<Event>,
+}
+
+impl Placed {
+    pub fn is_fault(&self) -> bool {
+        self.used.len() > self.best.len()
+    }
+}
+
+/// counts finesse faults from the changes of engine
+pub struct Finesse {
+    column: usize,
+    entered: Placement, // current block where it came in
+    used: Vec<Event>,   // moves on current block
+
+    pub faults: u32,
+    pub last: Option<Placed>,
+}
+
+impl Finesse {
+    /// current block counts from where it is now
+    pub fn new(engine: &Engine) -> Self {
+        Finesse {
+            column: engine.rules().size.0,
+            entered: engine.current_block().placement(),
+            used: Vec::new(),
+            faults: 0,
+            last: None,
+        }
+    }
+
+    /// count moves on current block again from `entered`
+    pub fn enter(&mut self, entered: Placement) {
+        self.entered = entered;
+        self.used.clear();
+    }
+
+    /// follow one change of engine, the block placed when it is locked
+    pub fn follow(&mut self, change: &Change) -> Option<&Placed> {
+        match change {
+            Change::Applied(_, event) if MOVES.contains(event) => self.used.push(*event),
+            Change::Entered(entered) => self.enter(*entered),
+            Change::Locked(placement) => {
+                let used = std::mem::take(&mut self.used);
+                let best = best_moves(self.entered, *placement, self.column)
+                    .unwrap_or_else(|| used.clone());
+                let placed = Placed {
+                    placement: *placement,
+                    used,
+                    best,
+                };
+
+                if placed.is_fault() {
+                    self.faults += 1;
+                }
+                self.last = Some(placed);
+                return self.last.as_ref();
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+/// finesse trainer: each block has a target to be placed at with the fewest moves
+pub struct Trainer {
+    pub target: Option<(Placement, Vec<Event>)>, // and the fewest moves to it
+    pub hits: u32,
+    pub misses: u32,
+    pub missed: Option<Vec<Event>>, // fewest moves to the target missed last
+
+    /// engine before the first input on current block, to try it again
+    pub retry: Option<Engine>,
+
+    /// picks the targets, same seed gives same targets
+    rng: ChaCha8Rng,
+}
+
+impl Trainer {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(4);
+        Trainer {
+            target: None,
+            hits: 0,
+            misses: 0,
+            missed: None,
+            retry: None,
+            rng,
+        }
+    }
+
+    /// a new target for a block came in
+    pub fn enter(&mut self, entered: Placement, column: usize) {
+        self.target = placements(entered, column).choose(&mut self.rng).cloned();
+    }
+
+    /// true if `placed` is at the target with the fewest moves
+    pub fn judge(&mut self, placed: &Placed) -> bool {
+        let hit = match &self.target {
+            Some((target, best)) => {
+                footprint(target) == footprint(&placed.placement) && placed.used.len() <= best.len()
+            }
+            None => true,
+        };
+
+        if hit {
+            self.hits += 1;
+            self.missed = None;
+        } else {
+            self.misses += 1;
+            self.missed = self.target.as_ref().map(|(_, best)| best.clone());
+        }
+        hit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::block::BLOCKS;
+    use crate::game::engine::Rules;
+
+    /// a block of `kind` where it comes in on a new game
+    fn entered(kind: usize) -> Placement {
+        Placement {
+            kind,
+            orientation: 0,
+            offset: Engine::new(Rules::new(42)).spawn_point(),
+        }
+    }
+
+    #[test]
+    fn one_placement_for_each_landing() {
+        // j, l, s, z, i, t, o on a board 10 wide
+        let landings = [34, 34, 17, 17, 17, 34, 9];
+        for (kind, count) in landings.into_iter().enumerate() {
+            let found = placements(entered(kind), 10);
+            assert_eq!(found.len(), count, "block {}", kind);
+
+            // the moves found lead there
+            for (placement, moves) in found {
+                let end = moves.iter().fold(entered(kind), |p, e| moved(&p, *e));
+                assert_eq!(footprint(&end), footprint(&placement));
+            }
+        }
+    }
+
+    #[test]
+    fn fewest_moves() {
+        let o = entered(6);
+        assert_eq!(best_moves(o, o, 10), Some(vec![]));
+
+        let mut left_wall = o;
+        left_wall.offset.x -= 4;
+        assert_eq!(best_moves(o, left_wall, 10), Some(vec![Event::Left; 4]));
+        // the same cells turned any way
+        left_wall.orientation = 2;
+        assert_eq!(best_moves(o, left_wall, 10).map(|m| m.len()), Some(4));
+
+        let t = entered(5);
+        let flipped = moved(&t, Event::FlipRotate);
+        assert_eq!(best_moves(t, flipped, 10), Some(vec![Event::FlipRotate]));
+
+        let mut off_board = o;
+        off_board.offset.x += 20;
+        assert_eq!(best_moves(o, off_board, 10), None);
+    }
+
+    #[test]
+    fn extra_moves_are_faults() {
+        let mut engine = Engine::new(Rules::new(42));
+        let mut finesse = Finesse::new(&engine);
+
+        for event in [Event::Left, Event::Right, Event::HardDrop] {
+            engine.apply(event);
+        }
+        let mut placed = Vec::new();
+        for change in engine.take_changes() {
+            placed.extend(finesse.follow(&change).cloned());
+        }
+        assert_eq!(placed.len(), 1);
+        assert_eq!(placed[0].used, [Event::Left, Event::Right]);
+        assert!(placed[0].best.is_empty());
+        assert_eq!(finesse.faults, 1);
+
+        engine.apply(Event::HardDrop);
+        for change in engine.take_changes() {
+            finesse.follow(&change);
+        }
+        assert_eq!(finesse.faults, 1);
+    }
+
+    #[test]
+    fn trainer_targets_go_by_seed() {
+        let targets = |seed| {
+            let mut trainer = Trainer::new(seed);
+            (0..BLOCKS.len())
+                .map(|kind| {
+                    trainer.enter(entered(kind), 10);
+                    trainer.target.clone().unwrap().0
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(targets(42), targets(42));
+        assert_ne!(targets(42), targets(43));
+    }
+}
@@ -1,15 +1,20 @@
 use terminal::Color;
 
 use super::block;
+use super::bot;
+use super::engine;
+use super::event;
+use super::finesse;
+use super::menu;
 use super::painter;
+use super::replay;
+use super::royale;
 use super::state;
 
-use std::sync::{
-    atomic::{AtomicU16, Ordering},
-    Arc,
-};
+use std::cell::Cell;
+use std::sync::{atomic::Ordering, Arc};
 
-const PAUSE_PRINT: (Color, &'static str, u16, u16) = (
+const PAUSE_PRINT: (Color, &str, u16, u16) = (
     Color::Rgb(135, 206, 250),
     concat!(
         "██████╗\n",
@@ -26,11 +31,39 @@ const PAUSE_PRINT: (Color, &'static str, u16, u16) = (
 const NEXT_BLOCK_FRAME_WIDTH: u16 = (block::POINT_OF_BLOCK_COUNT * 2) as u16 + 2 + 1;
 const NEXT_BLOCK_FRAME_HEIGHT: u16 = block::POINT_OF_BLOCK_COUNT as u16 + 1 + 2 + 1;
 
+const HOLD_FRAME_WIDTH: u16 = NEXT_BLOCK_FRAME_WIDTH;
+const HOLD_FRAME_HEIGHT: u16 = NEXT_BLOCK_FRAME_HEIGHT;
+
+const REPLAY_FRAME_WIDTH: u16 = HOLD_FRAME_WIDTH;
+const REPLAY_FRAME_HEIGHT: u16 = 8;
+
+const FINESSE_FRAME_WIDTH: u16 = HOLD_FRAME_WIDTH;
+const FINESSE_FRAME_HEIGHT: u16 = 9;
+
+const ROYALE_FRAME_WIDTH: u16 = HOLD_FRAME_WIDTH;
+const ROYALE_FRAME_HEIGHT: u16 = 9;
+
+const OUT_COLOR: Color = Color::DarkGrey;
+/// dots of a braille character by (column, row) in it
+const BRAILLE_DOTS: [(usize, usize, u32); 8] = [
+    (0, 0, 0x01),
+    (0, 1, 0x02),
+    (0, 2, 0x04),
+    (1, 0, 0x08),
+    (1, 1, 0x10),
+    (1, 2, 0x20),
+    (0, 3, 0x40),
+    (1, 3, 0x80),
+];
+
+/// one line each below the title, empty for a blank line
+const RECORD_LABELS: [&str; 11] = [
+    "time", "", "line", "score", "speed", "", "piece", "pps", "kpp", "lpm", "apm",
+];
 const RECORD_LEFT_WIDTH: u16 = " time: ".len() as u16;
 const RECORD_RIGHT_WIDTH: u16 = 10;
 const RECORD_FRAME_WIDTH: u16 = RECORD_LEFT_WIDTH + RECORD_RIGHT_WIDTH + 2;
-const RECORD_FRAME_HEIGHT: u16 =
-    (std::mem::size_of::<state::Record>() / std::mem::size_of::<u32>()) as u16 + 2 + 2;
+const RECORD_FRAME_HEIGHT: u16 = RECORD_LABELS.len() as u16 + 1 + 1;
 
 const RIGHT_SIDE_WIDTH: u16 = {
     if NEXT_BLOCK_FRAME_WIDTH > RECORD_FRAME_WIDTH {
@@ -40,11 +73,24 @@ const RIGHT_SIDE_WIDTH: u16 = {
     }
 };
 
+const LEFT_SIDE_WIDTH: u16 = HOLD_FRAME_WIDTH + 1;
+
 const RECORD_COLOR: Color = Color::Red;
+const GARBAGE_COLOR: Color = Color::Red;
 const BOARDER_COLOR: Color = Color::White;
+const SELECTED_COLOR: Color = Color::Yellow;
+const CONFLICT_COLOR: Color = Color::Red;
+
+const PAUSE_HINT: &str = "enter: keys  s: save";
+const MENU_NAME_WIDTH: usize = 16;
 
-static TERMINAL_WIDTH: AtomicU16 = AtomicU16::new(0);
-static TERMINAL_HEIGHT: AtomicU16 = AtomicU16::new(0);
+/// ((current column, needed column), (current row, needed row))
+type SizeTest = ((u16, u16), (u16, u16));
+
+thread_local! {
+    /// (col, row), each game is drawn on a thread of its own and a server draws many
+    static TERMINAL_SIZE: Cell<(u16, u16)> = const { Cell::new((0, 0)) };
+}
 
 pub trait Frame {
     fn draw(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
@@ -63,29 +109,23 @@ pub trait Frame {
     fn draw_inner(painter: &Arc<painter::Painter>, state: &Arc<state::State>);
 
     fn flush_terminal_size(painter: &Arc<painter::Painter>) {
-        let (col, row) = painter.get_size().unwrap_or((0, 0));
-        TERMINAL_WIDTH.store(col, Ordering::Relaxed);
-        TERMINAL_HEIGHT.store(row, Ordering::Relaxed);
+        TERMINAL_SIZE.set(painter.get_size().unwrap_or((0, 0)));
     }
 
     fn get_terminal_size() -> (u16, u16) {
-        (
-            TERMINAL_WIDTH.load(Ordering::Relaxed),
-            TERMINAL_HEIGHT.load(Ordering::Relaxed),
-        )
+        TERMINAL_SIZE.get()
     }
 
     fn set_terminal_size(size: (u16, u16), painter: &Arc<painter::Painter>) {
         painter.resize(size).unwrap();
     }
 
-    fn test_terminal_size(
-        state: &Arc<state::State>,
-    ) -> Result<((u16, u16), (u16, u16)), ((u16, u16), (u16, u16))> {
+    fn test_terminal_size(state: &Arc<state::State>) -> Result<SizeTest, SizeTest> {
         let (col, row) = Self::get_terminal_size();
 
         let (c, r) = state.get_game_size();
-        let (c, r) = (c + 2 + RIGHT_SIDE_WIDTH, r + 2);
+        let count = state.seat.count;
+        let (c, r) = ((LEFT_SIDE_WIDTH + c + 2 + RIGHT_SIDE_WIDTH) * count, r + 2);
 
         if row >= r && col >= c {
             Ok(((col, c), (row, r)))
@@ -94,17 +134,20 @@ pub trait Frame {
         }
     }
 
-    /// (top, bottom, left, right, middle)
+    /// (top, bottom, left, right, middle), left side is not included,
+    /// boards sharing the terminal are centered in equal columns of it
     fn get_global_borders(state: &Arc<state::State>) -> (u16, u16, u16, u16, u16) {
         let ((col, c), (row, r)) = Self::test_terminal_size(state).unwrap_or_else(|_| {
             *state.message.lock().unwrap() = Some(String::from("terminal size is too small"));
             panic!("terminal size is too small");
         });
+        let state::Seat { index, count, .. } = state.seat;
+        let (col, c) = (col / count, c / count);
 
         let top = (row - r) / 2;
         let bottom = top + r - 1;
-        let left = (col - c) / 2;
-        let right = left + c - 1;
+        let left = index * col + (col - c) / 2 + LEFT_SIDE_WIDTH;
+        let right = left + c - LEFT_SIDE_WIDTH - 1;
         let middle = right - RIGHT_SIDE_WIDTH;
 
         (top, bottom, left, right, middle)
@@ -120,16 +163,29 @@ impl GameFrame {
             (left + right - width) / 2 + 1,
             (bottom + top - height) / 2 + 1,
         );
-        let it = s.split('\n').into_iter().map(|s| s.as_bytes());
+        let it = s.split('\n').map(|s| s.as_bytes());
 
         painter.clear((top, bottom, left, right)).unwrap();
         painter.multiple_writeln_at(color, pos, it).unwrap();
+
+        // keys and save are not for a versus game, one only watched or one played from afar
+        if state.is_versus() || state.spectator || state.remote {
+            return;
+        }
+        let width = (right - left + 1) as usize;
+        painter
+            .write_at(
+                BOARDER_COLOR,
+                (left, bottom),
+                format!("{:^width$}", PAUSE_HINT).as_bytes(),
+            )
+            .unwrap();
     }
 
     pub fn draw_blinking(
         painter: &Arc<painter::Painter>,
         state: &Arc<state::State>,
-        lines: &Vec<usize>,
+        lines: &[usize],
     ) {
         let (_, bottom, left, _) = Self::get_inner_borders(state);
         let block_count = state.get_size().0;
@@ -146,16 +202,44 @@ impl GameFrame {
         }
     }
 
+    /// full lines blink twice while engine is clearing them
+    pub fn draw_clearing(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let engine = state.engine.lock().unwrap();
+        let (lines, shown) = match engine.clearing() {
+            Some((lines, shown)) => (lines.to_vec(), shown),
+            None => return,
+        };
+        let delay = engine.rules().clear_delay;
+        drop(engine);
+
+        if (shown.as_nanos() * 4 / delay.as_nanos()).is_multiple_of(2) {
+            Self::draw_blinking(painter, state, &lines);
+        } else {
+            Self::draw_stacked(painter, state);
+        }
+    }
+
     pub fn draw_falling(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        Self::draw_color_falling(None, painter, state);
+        let engine = state.engine.lock().unwrap();
+        let block = engine.current_block();
+        let (color, points) = (*block.color(), *block.points());
+        drop(engine);
+
+        Self::draw_points(color, &points, painter, state);
+        *state.falling.lock().unwrap() = Some(points);
     }
 
+    /// erase the falling block where it was drawn last
     pub fn reset_falling(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        Self::draw_color_falling(Some(Color::Reset), painter, state);
+        let points = state.falling.lock().unwrap().take();
+        if let Some(points) = points {
+            Self::draw_points(Color::Reset, &points, painter, state);
+        }
     }
 
-    pub fn draw_color_falling(
-        color: Option<Color>,
+    fn draw_points(
+        color: Color,
+        points: &block::PointArray,
         painter: &Arc<painter::Painter>,
         state: &Arc<state::State>,
     ) {
@@ -164,11 +248,6 @@ impl GameFrame {
 
         let left_bottom = (left, bottom);
 
-        let lock = state.two_blocks.lock().unwrap();
-        let color = color.unwrap_or(*lock.current_block().color());
-        let points = lock.current_block().points();
-
-        // ! danger of dead lock: two_block and painter
         painter
             .draw_multiple_block_at(
                 color,
@@ -181,26 +260,99 @@ impl GameFrame {
             .unwrap();
     }
 
+    /// outline of where the trainer wants current block, dropped onto the stacked blocks
+    pub fn draw_target(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let target = match &*state.trainer.lock().unwrap() {
+            Some(finesse::Trainer {
+                target: Some((target, _)),
+                ..
+            }) => *target,
+            _ => return,
+        };
+
+        let engine = state.engine.lock().unwrap();
+        if !engine.is_valid_position(&target.points()) {
+            return;
+        }
+        let points = bot::landing(engine.stacked(), &target);
+        drop(engine);
+
+        let (_, bottom, left, _) = Self::get_inner_borders(state);
+        let row = state.get_size().1;
+        painter
+            .draw_multiple_ghost_at(
+                *block::BLOCKS[target.kind].color(),
+                (left, bottom),
+                points.iter().filter(|p| (p.y as usize) < row),
+            )
+            .unwrap();
+    }
+
+    /// outline of where the bot would place current block, when hints are shown
+    pub fn draw_hint(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        if !state.hint.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let mut hinted = state.hinted.lock().unwrap();
+        let engine = state.engine.lock().unwrap();
+        // searched once for each block
+        let points = *hinted
+            .get_or_insert_with(|| bot::Bot::default().best(&engine).map(|(points, _)| points));
+        let color = *engine.current_block().color();
+        drop(engine);
+        drop(hinted);
+
+        let points = match points {
+            Some(points) => points,
+            None => return,
+        };
+        let (_, bottom, left, _) = Self::get_inner_borders(state);
+        let row = state.get_size().1;
+        painter
+            .draw_multiple_hint_at(
+                color,
+                (left, bottom),
+                points.iter().filter(|p| (p.y as usize) < row),
+            )
+            .unwrap();
+    }
+
+    /// garbage waiting to rise, as a bar up the left border from the floor
+    pub fn draw_garbage(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let (top, bottom, left, _) = Self::get_borders(state);
+        let pending = state.engine.lock().unwrap().pending_garbage() as u16;
+
+        for y in top + 1..bottom {
+            let color = if bottom - y <= pending {
+                GARBAGE_COLOR
+            } else {
+                BOARDER_COLOR
+            };
+            painter
+                .write_at(
+                    color,
+                    (left, y),
+                    painter::VERTICAL_BAR.to_string().as_bytes(),
+                )
+                .unwrap();
+        }
+    }
+
     pub fn draw_stacked(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
         let (_, bottom, left, _) = Self::get_inner_borders(state);
         let left_bottom = (left, bottom);
 
-        // ! danger of dead lock: stacked_blocks and painter
-        let lock = state.stacked_blocks.lock().unwrap();
-        let it = lock
-            .colors
-            .iter()
-            .enumerate()
-            .map(|(y, line)| {
-                line.iter().enumerate().filter_map(move |(x, color)| {
-                    if let Color::Reset = color {
-                        None
-                    } else {
-                        Some((*color, block::Point::new(x as isize, y as isize)))
-                    }
-                })
+        let colors = state.engine.lock().unwrap().stacked().colors().to_vec();
+        let it = colors.iter().enumerate().flat_map(|(y, line)| {
+            line.iter().enumerate().filter_map(move |(x, color)| {
+                if let Color::Reset = color {
+                    None
+                } else {
+                    Some((*color, block::Point::new(x as isize, y as isize)))
+                }
             })
-            .flatten();
+        });
 
         painter
             .draw_multiple_color_block_at(left_bottom, it)
@@ -219,13 +371,32 @@ impl Frame for GameFrame {
         painter
             .draw_rect(BOARDER_COLOR, (top, bottom, left, right))
             .unwrap();
+
+        if state.is_versus() {
+            let name = format!(" {} ", state.seat.name);
+            let width = (right - left - 1) as usize;
+            let pos = (
+                left + 1 + (width.saturating_sub(name.chars().count()) / 2) as u16,
+                top,
+            );
+            painter
+                .write_at(BOARDER_COLOR, pos, name.as_bytes())
+                .unwrap();
+        }
+        Self::draw_garbage(painter, state);
     }
 
     fn draw_inner(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
         let (top, bottom, left, right) = Self::get_inner_borders(state);
         painter.clear((top, bottom, left, right)).unwrap();
+        state.falling.lock().unwrap().take();
         Self::draw_stacked(painter, state);
-        Self::draw_falling(painter, state);
+        if state.engine.lock().unwrap().clearing().is_none() {
+            Self::draw_hint(painter, state);
+            Self::draw_target(painter, state);
+            Self::draw_falling(painter, state);
+        }
+        Self::draw_clearing(painter, state);
     }
 }
 
@@ -237,25 +408,6 @@ impl RecordFrame {
         let h = secs / 60 / 60;
         format!("{h:02}:{m:02}:{s:02}")
     }
-
-    pub fn draw_time(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        let (top, _, left, _) = Self::get_inner_borders(state);
-        let pos = (left + RECORD_LEFT_WIDTH, top + 2);
-        let secs = state.record.lock().unwrap().secs;
-
-        painter
-            .write_at(
-                RECORD_COLOR,
-                pos,
-                format!(
-                    "{:^width$}",
-                    Self::secs_to_string(secs),
-                    width = RECORD_RIGHT_WIDTH as usize
-                )
-                .as_bytes(),
-            )
-            .unwrap();
-    }
 }
 
 impl Frame for RecordFrame {
@@ -293,35 +445,52 @@ impl Frame for RecordFrame {
         let (top, _, left, _) = Self::get_inner_borders(state);
         let pos = (left, top + 1);
 
-        let state::Record {
+        let engine = state.engine.lock().unwrap();
+        let engine::Record {
             secs,
             line,
             score,
             speed,
-        } = *state.record.lock().unwrap();
+            pieces,
+            ..
+        } = *engine.record();
+        let engine::Stats { pps, kpp, lpm, apm } = engine.stats();
+        drop(engine);
 
-        let record_string = format!(
-            concat!(
-                " \n",
-                " time: {:^width$}\n",
-                " \n",
-                " line: {:^width$}\n",
-                " score:{:^width$}\n",
-                " speed:{:^width$}"
-            ),
+        let values = [
             Self::secs_to_string(secs),
-            line,
-            score,
-            speed,
-            width = RECORD_RIGHT_WIDTH as usize
-        );
+            String::new(),
+            line.to_string(),
+            score.to_string(),
+            speed.to_string(),
+            String::new(),
+            pieces.to_string(),
+            format!("{:.2}", pps),
+            format!("{:.2}", kpp),
+            format!("{:.1}", lpm),
+            format!("{:.1}", apm),
+        ];
+        let lines: Vec<String> = RECORD_LABELS
+            .iter()
+            .zip(values)
+            .map(|(label, value)| {
+                let label = if label.is_empty() {
+                    String::new()
+                } else {
+                    format!("{}:", label)
+                };
+                format!(
+                    " {:<left$}{:^right$}",
+                    label,
+                    value,
+                    left = RECORD_LEFT_WIDTH as usize - 1,
+                    right = RECORD_RIGHT_WIDTH as usize
+                )
+            })
+            .collect();
 
         painter
-            .multiple_writeln_at(
-                RECORD_COLOR,
-                pos,
-                record_string.split('\n').into_iter().map(|s| s.as_bytes()),
-            )
+            .multiple_writeln_at(RECORD_COLOR, pos, lines.iter().map(|s| s.as_bytes()))
             .unwrap();
     }
 }
@@ -329,27 +498,26 @@ impl Frame for RecordFrame {
 pub struct NextBlockFrame;
 
 impl NextBlockFrame {
-    pub fn draw_next(
-        color: Option<Color>,
-        painter: &Arc<painter::Painter>,
-        state: &Arc<state::State>,
-    ) {
+    pub fn draw_next(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
         let (_, bottom, left, _) = Self::get_inner_borders(state);
         let left_bottom = (
             left + (NEXT_BLOCK_FRAME_WIDTH - (block::POINT_OF_BLOCK_COUNT as u16 * 2)) / 2,
             bottom + 1 - (NEXT_BLOCK_FRAME_HEIGHT - block::POINT_OF_BLOCK_COUNT as u16) / 2,
         );
 
-        let color = color.unwrap_or(*state.two_blocks.lock().unwrap().next_block().color());
-        let points = *state.two_blocks.lock().unwrap().next_block().points();
+        let engine = state.engine.lock().unwrap();
+        let (color, points) = (*engine.next_block().color(), *engine.next_block().points());
+        drop(engine);
 
         painter
             .draw_multiple_block_at(color, left_bottom, points.iter())
             .unwrap();
     }
 
+    /// clear below the title
     pub fn reset_inner(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        Self::draw_next(Some(Color::Reset), painter, state);
+        let (top, bottom, left, right) = Self::get_inner_borders(state);
+        painter.clear((top + 1, bottom, left, right)).unwrap();
     }
 }
 
@@ -385,6 +553,542 @@ impl Frame for NextBlockFrame {
     }
 
     fn draw_inner(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        Self::draw_next(None, painter, state);
+        Self::draw_next(painter, state);
+    }
+}
+
+pub struct HoldFrame;
+
+impl HoldFrame {
+    pub fn draw_hold(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let (_, bottom, left, _) = Self::get_inner_borders(state);
+        let left_bottom = (
+            left + (HOLD_FRAME_WIDTH - (block::POINT_OF_BLOCK_COUNT as u16 * 2)) / 2,
+            bottom + 1 - (HOLD_FRAME_HEIGHT - block::POINT_OF_BLOCK_COUNT as u16) / 2,
+        );
+
+        let engine = state.engine.lock().unwrap();
+        if let Some(block) = engine.hold_block() {
+            let (color, points) = (*block.color(), *block.points());
+            drop(engine);
+
+            painter
+                .draw_multiple_block_at(color, left_bottom, points.iter())
+                .unwrap();
+        }
+    }
+
+    /// clear below the title
+    pub fn reset_inner(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let (top, bottom, left, right) = Self::get_inner_borders(state);
+        painter.clear((top + 1, bottom, left, right)).unwrap();
+    }
+}
+
+impl Frame for HoldFrame {
+    fn get_borders(state: &Arc<state::State>) -> (u16, u16, u16, u16) {
+        let (top, _, game_left, _, _) = Self::get_global_borders(state);
+        let (bottom, right) = (top + HOLD_FRAME_HEIGHT - 1, game_left - 1);
+        let left = right - HOLD_FRAME_WIDTH;
+        (top, bottom, left, right)
+    }
+
+    fn draw_border(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let (top, bottom, left, right) = Self::get_borders(state);
+
+        painter
+            .draw_rect(BOARDER_COLOR, (top, bottom, left, right))
+            .unwrap();
+
+        let (top, _, left, _) = Self::get_inner_borders(state);
+
+        painter
+            .write_at(
+                BOARDER_COLOR,
+                (left, top),
+                format!(" {:^width$}", "HOLD", width = HOLD_FRAME_WIDTH as usize - 2).as_bytes(),
+            )
+            .unwrap();
+    }
+
+    fn draw_inner(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        Self::draw_hold(painter, state);
+    }
+}
+
+/// key remapping menu, covers all other frames
+pub struct MenuFrame;
+
+impl Frame for MenuFrame {
+    fn get_borders(state: &Arc<state::State>) -> (u16, u16, u16, u16) {
+        let (top, bottom, left, right, _) = Self::get_global_borders(state);
+        (top, bottom, left - LEFT_SIDE_WIDTH, right)
+    }
+
+    fn draw_border(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let (top, bottom, left, right) = Self::get_borders(state);
+
+        painter.clear((top, bottom, left, right)).unwrap();
+        painter
+            .draw_rect(BOARDER_COLOR, (top, bottom, left, right))
+            .unwrap();
+    }
+
+    fn draw_inner(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let (top, bottom, left, right) = Self::get_inner_borders(state);
+        let width = (right - left + 1) as usize;
+        painter.clear((top, bottom, left, right)).unwrap();
+
+        let menu = match *state.screen.lock().unwrap() {
+            menu::Screen::KeyMenu(menu) => menu,
+            _ => return,
+        };
+        let keymap = state.config.lock().unwrap().keymap.clone();
+        let conflicts = keymap.conflicts();
+
+        painter
+            .write_at(
+                BOARDER_COLOR,
+                (left, top),
+                format!("{:^width$}", "KEYS").as_bytes(),
+            )
+            .unwrap();
+
+        for (i, action) in event::ACTIONS.iter().enumerate() {
+            let keys = keymap.keys(*action);
+            let conflict = keys.iter().any(|k| conflicts.contains(k));
+            let keys: Vec<String> = keys.iter().map(|k| k.to_string()).collect();
+
+            let selected = i == menu.cursor;
+            let line = format!(
+                " {} {:<name$}{}{}",
+                if selected { '>' } else { ' ' },
+                action.name(),
+                if conflict { "! " } else { "  " },
+                if selected && menu.waiting.is_some() {
+                    String::from("press a key ...")
+                } else {
+                    keys.join(", ")
+                },
+                name = MENU_NAME_WIDTH,
+            );
+            let line: String = line.chars().take(width).collect();
+
+            let color = if selected {
+                SELECTED_COLOR
+            } else if conflict {
+                CONFLICT_COLOR
+            } else {
+                BOARDER_COLOR
+            };
+            painter
+                .write_at(color, (left, top + 2 + i as u16), line.as_bytes())
+                .unwrap();
+        }
+
+        if let Some(action) = menu.refused {
+            let line = format!(" {} keeps one key at least", action.name());
+            let line: String = line.chars().take(width).collect();
+            painter
+                .write_at(CONFLICT_COLOR, (left, bottom - 4), line.as_bytes())
+                .unwrap();
+        }
+
+        if !conflicts.is_empty() {
+            let keys: Vec<String> = conflicts.iter().map(|k| k.to_string()).collect();
+            let line = format!(" conflict: {}", keys.join(", "));
+            let line: String = line.chars().take(width).collect();
+            painter
+                .write_at(CONFLICT_COLOR, (left, bottom - 3), line.as_bytes())
+                .unwrap();
+        }
+
+        let help = concat!(
+            " up/down: select   enter: replace   tab: add\n",
+            " backspace: clear  esc: save and back",
+        );
+        painter
+            .multiple_writeln_at(
+                BOARDER_COLOR,
+                (left, bottom - 1),
+                help.split('\n').map(|s| s.as_bytes()),
+            )
+            .unwrap();
+    }
+}
+
+/// replay viewer status, below hold frame
+pub struct ReplayFrame;
+
+impl Frame for ReplayFrame {
+    fn get_borders(state: &Arc<state::State>) -> (u16, u16, u16, u16) {
+        let (_, hold_bottom, left, right) = HoldFrame::get_borders(state);
+        let top = hold_bottom + 1;
+        (top, top + REPLAY_FRAME_HEIGHT - 1, left, right)
+    }
+
+    fn draw_border(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let (top, bottom, left, right) = Self::get_borders(state);
+
+        painter
+            .draw_rect(BOARDER_COLOR, (top, bottom, left, right))
+            .unwrap();
+
+        let (top, _, left, _) = Self::get_inner_borders(state);
+
+        painter
+            .write_at(
+                BOARDER_COLOR,
+                (left, top),
+                format!(
+                    " {:^width$}",
+                    "REPLAY",
+                    width = REPLAY_FRAME_WIDTH as usize - 2
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+    }
+
+    fn draw_inner(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let (top, bottom, left, right) = Self::get_inner_borders(state);
+        let width = (right - left + 1) as usize;
+
+        let status = match *state.replay.lock().unwrap() {
+            Some(status) => status,
+            None => return,
+        };
+        let piece = state.engine.lock().unwrap().pieces() + u32::from(!status.ended);
+
+        let replay::Status {
+            paused,
+            ended,
+            goto,
+            ..
+        } = status;
+        let mode = if ended {
+            "end"
+        } else if paused {
+            "pause"
+        } else {
+            "play"
+        };
+        let goto = goto.map_or(String::new(), |n| format!("go {}_", n));
+
+        let status_string = format!(
+            concat!(
+                "{:^width$}\n",
+                "{:^width$}\n",
+                "{:^width$}\n",
+                "{:^width$}\n",
+                "{:^width$}",
+            ),
+            mode,
+            format!("{}x", status.speed()),
+            format!("piece {}", piece),
+            "",
+            goto,
+            width = width
+        );
+
+        painter.clear((top + 1, bottom, left, right)).unwrap();
+        painter
+            .multiple_writeln_at(
+                RECORD_COLOR,
+                (left, top + 1),
+                status_string.split('\n').map(|s| s.as_bytes()),
+            )
+            .unwrap();
+    }
+}
+
+fn move_symbol(event: event::Event) -> char {
+    match event {
+        event::Event::Left => '←',
+        event::Event::Right => '→',
+        event::Event::ClockRotate => '↻',
+        event::Event::InverseRotate => '↺',
+        event::Event::FlipRotate => '⇅',
+        _ => '?',
+    }
+}
+
+/// finesse faults of a live game, or hits and misses of the trainer, below hold frame
+pub struct FinesseFrame;
+
+impl Frame for FinesseFrame {
+    fn get_borders(state: &Arc<state::State>) -> (u16, u16, u16, u16) {
+        let (_, hold_bottom, left, right) = HoldFrame::get_borders(state);
+        let top = hold_bottom + 1;
+        (top, top + FINESSE_FRAME_HEIGHT - 1, left, right)
+    }
+
+    fn draw_border(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let (top, bottom, left, right) = Self::get_borders(state);
+
+        painter
+            .draw_rect(BOARDER_COLOR, (top, bottom, left, right))
+            .unwrap();
+
+        let (top, _, left, _) = Self::get_inner_borders(state);
+        let title = if state.trainer.lock().unwrap().is_some() {
+            "TRAINER"
+        } else {
+            "FINESSE"
+        };
+
+        painter
+            .write_at(
+                BOARDER_COLOR,
+                (left, top),
+                format!(
+                    " {:^width$}",
+                    title,
+                    width = FINESSE_FRAME_WIDTH as usize - 2
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+    }
+
+    fn draw_inner(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let (top, bottom, left, right) = Self::get_inner_borders(state);
+        let width = (right - left + 1) as usize;
+
+        let last = state.finesse.lock().unwrap().last.clone();
+        let used = last.as_ref().map_or(0, |placed| placed.used.len());
+
+        // the last block placed is flagged if it missed the target or took more moves than needed
+        let (counts, best) = match &*state.trainer.lock().unwrap() {
+            Some(trainer) => (
+                [
+                    format!(" hit {:>width$}", trainer.hits, width = width - 5),
+                    format!(" miss{:>width$}", trainer.misses, width = width - 5),
+                ],
+                trainer.missed.clone(),
+            ),
+            None => (
+                [
+                    format!(
+                        " fault{:>width$}",
+                        state.finesse.lock().unwrap().faults,
+                        width = width - 6
+                    ),
+                    String::new(),
+                ],
+                last.filter(|placed| placed.is_fault())
+                    .map(|placed| placed.best),
+            ),
+        };
+        let flag = match best {
+            Some(best) => [
+                format!(" used{:>width$}", used, width = width - 5),
+                format!(" best{:>width$}", best.len(), width = width - 5),
+                format!(
+                    " {}",
+                    best.iter().map(|e| move_symbol(*e)).collect::<String>()
+                ),
+            ],
+            None => Default::default(),
+        };
+
+        painter.clear((top + 1, bottom, left, right)).unwrap();
+        painter
+            .multiple_writeln_at(
+                RECORD_COLOR,
+                (left, top + 1),
+                counts.iter().map(|s| s.as_bytes()),
+            )
+            .unwrap();
+        painter
+            .multiple_writeln_at(
+                CONFLICT_COLOR,
+                (left, top + 4),
+                flag.iter().map(|s| s.as_bytes()),
+            )
+            .unwrap();
+    }
+}
+
+/// targeting, knock outs and boards left of a battle royale, below hold frame
+pub struct RoyaleFrame;
+
+impl Frame for RoyaleFrame {
+    fn get_borders(state: &Arc<state::State>) -> (u16, u16, u16, u16) {
+        let (_, hold_bottom, left, right) = HoldFrame::get_borders(state);
+        let top = hold_bottom + 1;
+        (top, top + ROYALE_FRAME_HEIGHT - 1, left, right)
+    }
+
+    fn draw_border(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let (top, bottom, left, right) = Self::get_borders(state);
+
+        painter
+            .draw_rect(BOARDER_COLOR, (top, bottom, left, right))
+            .unwrap();
+
+        let (top, _, left, _) = Self::get_inner_borders(state);
+
+        painter
+            .write_at(
+                BOARDER_COLOR,
+                (left, top),
+                format!(
+                    " {:^width$}",
+                    "ROYALE",
+                    width = ROYALE_FRAME_WIDTH as usize - 2
+                )
+                .as_bytes(),
+            )
+            .unwrap();
+    }
+
+    fn draw_inner(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let (top, bottom, left, right) = Self::get_inner_borders(state);
+        let width = (right - left + 1) as usize;
+
+        let status = match &*state.royale.lock().unwrap() {
+            Some(status) => status.clone(),
+            None => return,
+        };
+        let royale::Status {
+            targeting,
+            target,
+            attackers,
+            kos,
+            badges,
+            alive,
+            total,
+        } = status;
+
+        let lines = [
+            format!("{:^width$}", targeting.name()),
+            format!(" → {}", target.as_deref().unwrap_or("none")),
+            format!(" ← {}", attackers),
+            format!(" ko{:>width$}", kos, width = width - 3),
+            format!(" ★{:>width$}", badges, width = width - 2),
+            format!("{:^width$}", format!("{}/{}", alive, total)),
+        ];
+
+        painter.clear((top + 1, bottom, left, right)).unwrap();
+        painter
+            .multiple_writeln_at(
+                RECORD_COLOR,
+                (left, top + 1),
+                lines.iter().map(|line| {
+                    let end = line
+                        .char_indices()
+                        .nth(width)
+                        .map_or(line.len(), |(i, _)| i);
+                    &line.as_bytes()[..end]
+                }),
+            )
+            .unwrap();
+    }
+}
+
+/// bot boards of a battle royale, small on both sides of the board of the player
+pub struct RivalsFrame;
+
+impl RivalsFrame {
+    /// (column, row) of a bot board with the line below it
+    fn mini_size(state: &Arc<state::State>) -> (u16, u16) {
+        let (col, row) = state.get_size();
+        (col.div_ceil(2) as u16, row.div_ceil(4) as u16 + 1)
+    }
+
+    /// (left, top) of each of `count` bot boards, left and right in turn from the board
+    /// of the player out, or the (column, row) the terminal needs for them all
+    pub fn slots(state: &Arc<state::State>, count: usize) -> Result<Vec<(u16, u16)>, (u16, u16)> {
+        let ((col, c), (row, _)) = GameFrame::test_terminal_size(state).unwrap_or_else(|e| e);
+        let (width, height) = Self::mini_size(state);
+        let (pitch_col, pitch_row) = (width + 1, height + 1);
+
+        // a column apart from the board of the player
+        let side = (col.saturating_sub(c) / 2).saturating_sub(1);
+        let down = (row / pitch_row).max(1);
+        let per_side = count.div_ceil(2) as u16;
+        let across = per_side.div_ceil(down);
+        if across * pitch_col > side {
+            return Err((c + 2 * (across * pitch_col + 1), row));
+        }
+
+        let top = (row - per_side.min(down) * pitch_row) / 2;
+        let (player_left, player_right) = ((col - c) / 2, (col - c) / 2 + c);
+        Ok((0..count)
+            .map(|i| {
+                let k = (i / 2) as u16;
+                let (x, y) = (k / down, k % down);
+                // the record frame goes a column past the right side
+                let left = if i % 2 == 0 {
+                    player_left - (x + 1) * pitch_col
+                } else {
+                    player_right + 2 + x * pitch_col
+                };
+                (left, top + y * pitch_row)
+            })
+            .collect())
+    }
+
+    /// bot board `i` at `slot` in braille, 2 columns and 4 rows to a character,
+    /// yellow if the player targets it, red if it targets the player, grey once out
+    pub fn draw_mini(
+        painter: &Arc<painter::Painter>,
+        royale: &royale::Royale,
+        i: usize,
+        slot: (u16, u16),
+    ) {
+        let bot = &royale.players[i + 1];
+        let engine = &royale.opponents[i].engine;
+
+        let mut lines = engine.stacked().lines().to_vec();
+        if !bot.is_out() {
+            for p in engine.current_block().points() {
+                if p.x >= 0 && p.y >= 0 && (p.y as usize) < lines.len() {
+                    lines[p.y as usize] |= 1 << p.x;
+                }
+            }
+        }
+        let (col, row) = (engine.stacked().get_column(), lines.len());
+        let width = col.div_ceil(2);
+
+        let mut text: Vec<String> = (0..row.div_ceil(4))
+            .map(|cy| {
+                (0..width)
+                    .map(|cx| {
+                        let bits = BRAILLE_DOTS
+                            .iter()
+                            .filter(|(dx, dy, _)| {
+                                let (x, down) = (2 * cx + dx, 4 * cy + dy);
+                                x < col && down < row && lines[row - 1 - down] & 1 << x != 0
+                            })
+                            .fold(0, |bits, (.., bit)| bits | bit);
+                        match bits {
+                            0 => ' ',
+                            bits => char::from_u32(0x2800 + bits).unwrap(),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let label = match bot.place {
+            Some(place) => format!("#{}", place),
+            None if bot.badges > 0 => format!("★{}", bot.badges),
+            None => String::new(),
+        };
+        text.push(format!("{:^width$}", label));
+
+        let color = if bot.is_out() {
+            OUT_COLOR
+        } else if royale.players[0].target == Some(i + 1) {
+            SELECTED_COLOR
+        } else if bot.target == Some(0) {
+            CONFLICT_COLOR
+        } else {
+            BOARDER_COLOR
+        };
+        painter
+            .multiple_writeln_at(color, slot, text.iter().map(|s| s.as_bytes()))
+            .unwrap();
     }
 }
@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+/// how a t locked, by the corners around its center
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Spin {
+    None,
+    Mini,
+    Full,
+}
+
+/// a lock that clears lines, as the attack table sees it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Clear {
+    pub lines: u32,
+    pub spin: Spin,
+    pub back_to_back: bool, // a tetris or t-spin right after another, other clears between break it
+    pub combo: u32,         // clears in a row before this one
+    pub perfect: bool,      // nothing is left on the board
+}
+
+impl Clear {
+    /// tetrises and t-spins keep back-to-back going
+    pub fn is_difficult(&self) -> bool {
+        self.lines >= 4 || self.spin != Spin::None
+    }
+}
+
+/// how clears turn into garbage lines, and how messy garbage rises
+///
+/// ~~~text
+/// attack_lines = 0, 1, 2, 4
+/// attack_t_spin = 2, 4, 6
+/// attack_t_spin_mini = 0, 1
+/// attack_back_to_back = 1
+/// attack_combo = 0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5
+/// attack_perfect_clear = 10
+/// garbage_messiness = 0
+/// ~~~
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Garbage {
+    pub lines: [u32; 4],       // clearing 1 to 4 lines
+    pub t_spin: [u32; 3],      // t-spin clearing 1 to 3 lines
+    pub t_spin_mini: [u32; 2], // mini t-spin clearing 1 or 2 lines
+    pub back_to_back: u32,     // added to a difficult clear right after another
+    pub combo: Vec<u32>,       // added by clears in a row before, the last one repeats
+    pub perfect_clear: u32,    // added when nothing is left
+    pub messiness: f64,        // chance the hole moves from one garbage line to the next
+}
+
+impl Default for Garbage {
+    fn default() -> Self {
+        Garbage {
+            lines: [0, 1, 2, 4],
+            t_spin: [2, 4, 6],
+            t_spin_mini: [0, 1],
+            back_to_back: 1,
+            combo: vec![0, 1, 1, 2, 2, 3, 3, 4, 4, 4, 5],
+            perfect_clear: 10,
+            messiness: 0.0,
+        }
+    }
+}
+
+/// `values` as exactly `N` numbers, or as many as there are if `N` is 0
+fn numbers<const N: usize>(values: &str) -> Result<Vec<u32>, String> {
+    let numbers = values
+        .split(',')
+        .map(|v| v.trim().parse())
+        .collect::<Result<Vec<u32>, _>>()
+        .map_err(|_| format!("invalid numbers `{}`", values))?;
+    if N != 0 && numbers.len() != N {
+        return Err(format!("expect {} numbers, got `{}`", N, values));
+    }
+    Ok(numbers)
+}
+
+impl Garbage {
+    /// garbage lines `clear` sends
+    pub fn attack(&self, clear: &Clear) -> u32 {
+        let get = |table: &[u32], i: u32| table.get(i as usize).copied().unwrap_or(0);
+        let lines = match clear.spin {
+            Spin::Full => get(&self.t_spin, clear.lines.saturating_sub(1)),
+            Spin::Mini => get(&self.t_spin_mini, clear.lines.saturating_sub(1)),
+            Spin::None => get(&self.lines, clear.lines.saturating_sub(1)),
+        };
+        let combo = match self.combo.last() {
+            Some(last) => self.combo.get(clear.combo as usize).unwrap_or(last),
+            None => &0,
+        };
+
+        lines
+            + combo
+            + if clear.back_to_back {
+                self.back_to_back
+            } else {
+                0
+            }
+            + if clear.perfect { self.perfect_clear } else { 0 }
+    }
+
+    /// set the setting of a config line, false if `name` is not one
+    pub fn set(&mut self, name: &str, value: &str) -> Result<bool, String> {
+        match name {
+            "attack_lines" => self.lines.copy_from_slice(&numbers::<4>(value)?),
+            "attack_t_spin" => self.t_spin.copy_from_slice(&numbers::<3>(value)?),
+            "attack_t_spin_mini" => self.t_spin_mini.copy_from_slice(&numbers::<2>(value)?),
+            "attack_back_to_back" => self.back_to_back = numbers::<1>(value)?[0],
+            "attack_combo" => self.combo = numbers::<0>(value)?,
+            "attack_perfect_clear" => self.perfect_clear = numbers::<1>(value)?[0],
+            "garbage_messiness" => {
+                self.messiness = value
+                    .parse()
+                    .ok()
+                    .filter(|m| (0.0..=1.0).contains(m))
+                    .ok_or_else(|| format!("messiness `{}` is not from 0 to 1", value))?
+            }
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+impl fmt::Display for Garbage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |numbers: &[u32]| {
+            numbers
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        writeln!(f, "attack_lines = {}", join(&self.lines))?;
+        writeln!(f, "attack_t_spin = {}", join(&self.t_spin))?;
+        writeln!(f, "attack_t_spin_mini = {}", join(&self.t_spin_mini))?;
+        writeln!(f, "attack_back_to_back = {}", self.back_to_back)?;
+        writeln!(f, "attack_combo = {}", join(&self.combo))?;
+        writeln!(f, "attack_perfect_clear = {}", self.perfect_clear)?;
+        writeln!(f, "garbage_messiness = {}", self.messiness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_read_back_as_written() {
+        let garbage = Garbage {
+            lines: [0, 1, 3, 5],
+            combo: vec![0, 2],
+            messiness: 0.25,
+            ..Default::default()
+        };
+
+        let mut read = Garbage::default();
+        for line in garbage.to_string().lines() {
+            let (name, value) = line.split_once(" = ").unwrap();
+            assert_eq!(read.set(name, value), Ok(true));
+        }
+        assert_eq!(read, garbage);
+    }
+
+    #[test]
+    fn bad_settings_are_refused() {
+        let mut garbage = Garbage::default();
+        assert!(garbage.set("attack_lines", "0, 1, 2").is_err());
+        assert!(garbage.set("attack_t_spin", "2, four, 6").is_err());
+        assert!(garbage.set("attack_back_to_back", "-1").is_err());
+        assert!(garbage.set("garbage_messiness", "1.5").is_err());
+        assert_eq!(garbage.set("attack_lines_", "0"), Ok(false));
+        assert_eq!(garbage, Garbage::default());
+    }
+
+    #[test]
+    fn attack_adds_up() {
+        let garbage = Garbage::default();
+        let tetris = Clear {
+            lines: 4,
+            spin: Spin::None,
+            back_to_back: false,
+            combo: 0,
+            perfect: false,
+        };
+        assert_eq!(garbage.attack(&tetris), 4);
+        let clear = Clear {
+            back_to_back: true,
+            combo: 3,
+            perfect: true,
+            ..tetris
+        };
+        assert_eq!(garbage.attack(&clear), 4 + 1 + 2 + 10);
+
+        let single = Clear { lines: 1, ..tetris };
+        assert_eq!(garbage.attack(&single), 0);
+        let t_spin = Clear {
+            spin: Spin::Full,
+            lines: 2,
+            ..tetris
+        };
+        assert_eq!(garbage.attack(&t_spin), 4);
+        // the last combo bonus goes on
+        let combo = Clear {
+            combo: 40,
+            ..single
+        };
+        assert_eq!(garbage.attack(&combo), 5);
+    }
+}
@@ -0,0 +1,355 @@
+use super::block::{MAX_COLUMN, MAX_ROW, POINT_OF_BLOCK_COUNT};
+use super::bot::{Bot, Weights};
+use super::engine::Rules;
+use super::sim;
+
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use std::fmt::{self, Write};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+
+const CHECKPOINT_VERSION: u32 = 1;
+
+/// share of the population a tournament picks from
+const TOURNAMENT: f64 = 0.1;
+/// share of the population replaced by offspring each generation
+const OFFSPRING: f64 = 0.3;
+/// chance an offspring has one weight moved, and how far at most
+const MUTATION: (f64, f32) = (0.05, 0.2);
+
+/// a weight set and the mean lines it clears, None until it plays
+#[derive(Clone, Copy, Debug)]
+pub struct Candidate {
+    pub weights: Weights,
+    pub fitness: Option<f64>,
+}
+
+/// a population of weight sets evolving over seeded games,
+/// every set plays the same games so their fitness can be compared
+///
+/// ~~~text
+/// tetris-train 1
+/// seed 0
+/// size 10 20
+/// games 5
+/// pieces 500
+/// generation 3
+/// candidate 180.4 -0.51 0.76 -0.35 -0.18
+/// ...
+/// ~~~
+#[derive(Clone, Debug)]
+pub struct Evolution {
+    pub seed: u64,
+    pub size: (usize, usize),
+    pub games: u64,  // games each weight set plays, of seeds counting up from `seed`
+    pub pieces: u32, // a game stops after this many pieces
+    pub generation: u32,
+    pub population: Vec<Candidate>,
+}
+
+/// a random direction, weights only matter relative to each other
+fn normalized(weights: [f32; 4]) -> Weights {
+    let norm = weights.iter().map(|w| w * w).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        Weights::from_array(weights.map(|w| w / norm))
+    } else {
+        Weights::default()
+    }
+}
+
+impl Evolution {
+    pub fn new(
+        seed: u64,
+        size: (usize, usize),
+        games: u64,
+        pieces: u32,
+        population: usize,
+    ) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let population = (0..population.max(2))
+            .map(|_| Candidate {
+                weights: normalized([(); 4].map(|_| rng.gen_range(-1.0..1.0))),
+                fitness: None,
+            })
+            .collect();
+
+        Evolution {
+            seed,
+            size,
+            games,
+            pieces,
+            generation: 0,
+            population,
+        }
+    }
+
+    pub fn best(&self) -> Option<&Candidate> {
+        self.population
+            .iter()
+            .filter(|c| c.fitness.is_some())
+            .max_by(|a, b| a.fitness.unwrap().total_cmp(&b.fitness.unwrap()))
+    }
+
+    /// play the games of every weight set without a fitness, on `threads` at once
+    pub fn evaluate(&mut self, threads: usize) -> Result<(), String> {
+        let rules = Rules {
+            size: self.size,
+            ..Rules::new(self.seed)
+        };
+        let jobs: Vec<(usize, u64)> = (0..self.population.len())
+            .filter(|i| self.population[*i].fitness.is_none())
+            .flat_map(|i| {
+                (self.seed..self.seed.saturating_add(self.games)).map(move |seed| (i, seed))
+            })
+            .collect();
+
+        let jobs = Mutex::new(jobs.into_iter());
+        let lines = Mutex::new(vec![0u64; self.population.len()]);
+        let population = &self.population;
+        let pieces = self.pieces;
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..threads.max(1))
+                .map(|_| {
+                    scope.spawn(|| -> Result<(), String> {
+                        loop {
+                            // the lock is let go before the game is played
+                            let job = jobs.lock().unwrap().next();
+                            let Some((i, seed)) = job else { break };
+                            let mut bot = Bot::new(population[i].weights);
+                            let played = sim::play(Rules { seed, ..rules }, &mut bot, pieces)?;
+                            lines.lock().unwrap()[i] += played.record.line as u64;
+                        }
+                        Ok(())
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .try_for_each(|worker| worker.join().unwrap())
+        })?;
+
+        let lines = lines.into_inner().unwrap();
+        for (candidate, lines) in self.population.iter_mut().zip(lines) {
+            if candidate.fitness.is_none() {
+                candidate.fitness = Some(lines as f64 / self.games.max(1) as f64);
+            }
+        }
+        Ok(())
+    }
+
+    /// the next generation: the weakest are replaced by offspring of tournament winners,
+    /// offspring have no fitness yet
+    pub fn breed(&mut self) {
+        self.generation += 1;
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        rng.set_stream(self.generation as u64);
+
+        let fitness = |c: &Candidate| c.fitness.unwrap_or(0.0);
+        let size = self.population.len();
+        let tournament = ((size as f64 * TOURNAMENT) as usize).max(2);
+        let count = ((size as f64 * OFFSPRING) as usize).max(1);
+
+        let offspring: Vec<Candidate> = (0..count)
+            .map(|_| {
+                let mut picked: Vec<&Candidate> = self
+                    .population
+                    .choose_multiple(&mut rng, tournament)
+                    .collect();
+                picked.sort_by(|a, b| fitness(b).total_cmp(&fitness(a)));
+                let (a, b) = (picked[0], picked[1]);
+
+                // the fitter parent pulls the child closer
+                let (fa, fb) = match (fitness(a) as f32, fitness(b) as f32) {
+                    (fa, fb) if fa + fb > 0.0 => (fa, fb),
+                    _ => (1.0, 1.0),
+                };
+                let (wa, wb) = (a.weights.to_array(), b.weights.to_array());
+                let mut child = [0, 1, 2, 3].map(|i| wa[i] * fa + wb[i] * fb);
+
+                if rng.gen_bool(MUTATION.0) {
+                    child[rng.gen_range(0..4)] += rng.gen_range(-MUTATION.1..MUTATION.1);
+                }
+                Candidate {
+                    weights: normalized(child),
+                    fitness: None,
+                }
+            })
+            .collect();
+
+        self.population
+            .sort_by(|a, b| fitness(b).total_cmp(&fitness(a)));
+        self.population.truncate(size - count);
+        self.population.extend(offspring);
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        fs::write(path, self.to_string()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// None if there is no checkpoint yet
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        match fs::read_to_string(path) {
+            Ok(s) => Self::parse(&s)
+                .map(Some)
+                .map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut lines = s.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+        let count = s.lines().filter(|l| !l.trim().is_empty()).count();
+
+        let mut field = |name: &str| -> Result<(usize, Vec<&str>), String> {
+            let (i, line) = lines.next().ok_or_else(|| format!("missing `{}`", name))?;
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some(word) if word == name => Ok((i, words.collect())),
+                _ => Err(format!("line {}: expect `{}`", i + 1, name)),
+            }
+        };
+        let number = |i: usize, w: &str| -> Result<u64, String> {
+            w.parse()
+                .map_err(|_| format!("line {}: invalid number `{}`", i + 1, w))
+        };
+
+        let (i, version) = field("tetris-train")?;
+        if version[..] != [CHECKPOINT_VERSION.to_string().as_str()] {
+            return Err(format!("line {}: unknown checkpoint version", i + 1));
+        }
+        let one = |(i, words): (usize, Vec<&str>)| -> Result<u64, String> {
+            match words[..] {
+                [w] => number(i, w),
+                _ => Err(format!("line {}: expect one number", i + 1)),
+            }
+        };
+        let small = |(i, words): (usize, Vec<&str>)| -> Result<u32, String> {
+            let n = one((i, words))?;
+            u32::try_from(n).map_err(|_| format!("line {}: {} is too large", i + 1, n))
+        };
+        let seed = one(field("seed")?)?;
+        let (i, words) = field("size")?;
+        let (column, row) = match words[..] {
+            [c, r] => (number(i, c)?, number(i, r)?),
+            _ => return Err(format!("line {}: expect `size <col> <row>`", i + 1)),
+        };
+        if !(POINT_OF_BLOCK_COUNT as u64..=MAX_COLUMN as u64).contains(&column)
+            || !(1..=MAX_ROW as u64).contains(&row)
+        {
+            return Err(format!("line {}: invalid size {}x{}", i + 1, column, row));
+        }
+        let size = (column as usize, row as usize);
+        let games = one(field("games")?)?;
+        let pieces = small(field("pieces")?)?;
+        let generation = small(field("generation")?)?;
+
+        // the lines after the six above are candidates, a bad one fails the checkpoint
+        let mut population = Vec::new();
+        for _ in 6..count {
+            let (i, words) = field("candidate")?;
+            let numbers: Vec<f64> = words
+                .iter()
+                .map(|w| w.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|_| format!("line {}: invalid candidate", i + 1))?;
+            match numbers[..] {
+                [fitness, h, l, o, b] => population.push(Candidate {
+                    weights: Weights::from_array([h, l, o, b].map(|w| w as f32)),
+                    fitness: Some(fitness),
+                }),
+                _ => return Err(format!("line {}: expect fitness and 4 weights", i + 1)),
+            }
+        }
+        if population.len() < 2 {
+            return Err(String::from("too few candidates"));
+        }
+
+        Ok(Evolution {
+            seed,
+            size,
+            games,
+            pieces,
+            generation,
+            population,
+        })
+    }
+}
+
+impl fmt::Display for Evolution {
+    /// only candidates with a fitness are written
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = String::new();
+        writeln!(s, "tetris-train {}", CHECKPOINT_VERSION)?;
+        writeln!(s, "seed {}", self.seed)?;
+        writeln!(s, "size {} {}", self.size.0, self.size.1)?;
+        writeln!(s, "games {}", self.games)?;
+        writeln!(s, "pieces {}", self.pieces)?;
+        writeln!(s, "generation {}", self.generation)?;
+        for candidate in &self.population {
+            if let Some(fitness) = candidate.fitness {
+                write!(s, "candidate {}", fitness)?;
+                for weight in candidate.weights.to_array() {
+                    write!(s, " {}", weight)?;
+                }
+                writeln!(s)?;
+            }
+        }
+        f.write_str(&s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint() -> Evolution {
+        let mut evolution = Evolution::new(3, (6, 12), 2, 20, 4);
+        evolution.evaluate(1).unwrap();
+        evolution
+    }
+
+    #[test]
+    fn checkpoint_reads_back() {
+        let evolution = checkpoint();
+        let read = Evolution::parse(&evolution.to_string()).unwrap();
+        assert_eq!(read.to_string(), evolution.to_string());
+        assert_eq!(read.population.len(), 4);
+    }
+
+    #[test]
+    fn bad_candidates_fail_the_checkpoint() {
+        let text = checkpoint().to_string();
+        let error = |s: &str| Evolution::parse(s).err().unwrap();
+
+        let broken = text.replacen("candidate", "candidate x", 2);
+        assert!(error(&broken).ends_with("invalid candidate"));
+        let cut = format!("{}candidate 1 0.5\n", text);
+        assert!(error(&cut).ends_with("expect fitness and 4 weights"));
+        let stray = format!("{}\nweights 1 2 3 4\n", text);
+        assert!(error(&stray).ends_with("expect `candidate`"));
+    }
+
+    #[test]
+    fn counts_are_checked() {
+        let text = checkpoint().to_string();
+        let error = |from: &str, to: &str| Evolution::parse(&text.replace(from, to)).err().unwrap();
+        assert!(error("pieces 20", "pieces 4294967296").ends_with("4294967296 is too large"));
+        assert!(error("generation 0", "generation -1").ends_with("invalid number `-1`"));
+        assert!(error("size 6 12", "size 6 100000").ends_with("invalid size 6x100000"));
+    }
+
+    #[test]
+    fn seeds_stop_at_the_last_one() {
+        let mut evolution = Evolution::new(u64::MAX, (6, 12), 2, 20, 2);
+        evolution.evaluate(1).unwrap();
+        assert!(evolution.best().is_some());
+    }
+}
@@ -1,24 +1,24 @@
+use super::engine::Change;
 use super::frame::Frame;
 use super::state;
-use super::{block, event, frame, painter};
+use super::{
+    arena, event, finesse, frame, menu, net, netplay, painter, player, replay, royale, save,
+    spectate, versus, viewer,
+};
 
-use std::borrow::BorrowMut;
-use std::ops::AddAssign;
 use std::sync::atomic::Ordering;
-use std::sync::{mpsc::Receiver, Arc};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
-use terminal::Event;
+use std::time::{Duration, Instant};
+use terminal::{Event, KeyCode};
 
-const TO_LEFT_POINT: block::Point = block::Point::new(-1, 0);
-const TO_RIGHT_POINT: block::Point = block::Point::new(1, 0);
-const TO_DROP_POINT: block::Point = block::Point::new(0, -1);
-const TO_RISE_POINT: block::Point = block::Point::new(0, 1);
-
-fn line_to_score(line: u32) -> u32 {
-    (1..=line).into_iter().sum()
-}
+/// how often engine is ticked when no key comes
+pub const TICK: Duration = Duration::from_millis(10);
+/// time between two inputs of the bot
+pub const AUTOPLAY_INTERVAL: Duration = Duration::from_millis(100);
 
+/// terminal frontend of engine: keys go in, changes are drawn
 #[derive(Default)]
 pub struct Handler {
     pub threads: Vec<JoinHandle<()>>,
@@ -33,20 +33,7 @@ impl Drop for Handler {
 }
 
 impl Handler {
-    pub fn is_valid_position(points: &[block::Point], state: &Arc<state::State>) -> bool {
-        let (col, row) = state.get_size();
-        let (col, _) = (col as isize, row as isize);
-
-        points
-            .iter()
-            .all(|p| 0 <= p.x && p.x < col && 0 <= p.y ) // p.y < row
-            && !state.stacked_blocks.lock().unwrap().is_overlapped(points)
-    }
-
-    fn resize(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        state.timer.pause();
-        state.handle_signal.store(false, Ordering::Relaxed);
-
+    pub fn resize(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
         let size = frame::GameFrame::get_terminal_size();
         frame::GameFrame::flush_terminal_size(painter);
 
@@ -60,248 +47,427 @@ impl Handler {
                 Self::quit(painter, state);
             }
         } else {
-            painter.clear_all().unwrap();
-            frame::GameFrame::draw(painter, state);
-            frame::RecordFrame::draw(painter, state);
-            frame::NextBlockFrame::draw(painter, state);
+            Self::draw_all(painter, state);
         }
+    }
 
-        state.handle_signal.store(true, Ordering::Release);
-        state.timer.resume();
+    pub fn draw_all(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        painter.clear_all().unwrap();
+        Self::draw_frames(painter, state);
     }
 
-    fn quit(_: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        state.quit_signal.store(true, Ordering::Relaxed);
+    /// every frame of one board, without clearing the terminal
+    pub fn draw_frames(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        frame::GameFrame::draw(painter, state);
+        frame::RecordFrame::draw(painter, state);
+        frame::NextBlockFrame::draw(painter, state);
+        frame::HoldFrame::draw(painter, state);
+        if state.replay.lock().unwrap().is_some() {
+            frame::ReplayFrame::draw(painter, state);
+        } else if state.royale.lock().unwrap().is_some() {
+            frame::RoyaleFrame::draw(painter, state);
+        } else {
+            frame::FinesseFrame::draw(painter, state);
+        }
+        Self::draw_screen(painter, state);
     }
 
-    fn pause(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        state.timer.pause();
-        frame::GameFrame::draw_pause(painter, state);
+    /// draw whatever covers the game frames
+    pub fn draw_screen(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let screen = *state.screen.lock().unwrap();
+        match screen {
+            menu::Screen::Game => {}
+            menu::Screen::Pause => frame::GameFrame::draw_pause(painter, state),
+            menu::Screen::KeyMenu(_) => frame::MenuFrame::draw(painter, state),
+        }
     }
 
-    fn resume(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        frame::GameFrame::draw_inner(painter, state);
-        state.timer.resume();
+    pub fn apply(event: event::Event, painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        match event {
+            event::Event::Unknow => {}
+            event::Event::Quit => Self::quit(painter, state),
+            event::Event::Toggle => Self::pause(painter, state),
+            event::Event::Hint => Self::hint(painter, state),
+            event::Event::Resize => Self::resize(painter, state),
+            _ => {
+                let mut engine = state.engine.lock().unwrap();
+                if let Some(trainer) = &mut *state.trainer.lock().unwrap() {
+                    // current block can be tried again from here
+                    if trainer.retry.is_none() {
+                        trainer.retry = Some(engine.clone());
+                    }
+                }
+                engine.apply(event);
+            }
+        }
     }
 
-    fn drop(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        // ! danger of dead lock: two_blocks, stacked_blocks
-        // ! drop to avoid dead lock
-        frame::GameFrame::reset_falling(painter, state);
+    /// draw what engine changed since the last update, true when the game is over
+    pub fn update(painter: &Arc<painter::Painter>, state: &Arc<state::State>) -> bool {
+        let changes = state.engine.lock().unwrap().take_changes();
+
+        let mut falling = false;
+        let mut over = false;
+        for change in changes {
+            let placed = state.finesse.lock().unwrap().follow(&change).cloned();
+            if let Some(moves) = &mut *state.moves.lock().unwrap() {
+                if matches!(change, Change::Held | Change::Locked(_)) {
+                    moves.push(change.clone());
+                }
+            }
 
-        let mut lock = state.two_blocks.lock().unwrap();
+            match change {
+                Change::Applied(time, event) => Self::record(time, event, state),
+                Change::Moved => falling = true,
+                Change::Entered(entered) => {
+                    if let Some(autoplay) = &mut *state.autoplay.lock().unwrap() {
+                        autoplay.plan = None;
+                    }
+                    state.hinted.lock().unwrap().take();
+                    let trained = match &mut *state.trainer.lock().unwrap() {
+                        Some(trainer) => {
+                            trainer.enter(entered, state.get_size().0);
+                            true
+                        }
+                        None => false,
+                    };
+                    if trained || state.hint.load(Ordering::Relaxed) {
+                        // erase the target and the hint of the last block
+                        frame::GameFrame::draw_inner(painter, state);
+                    }
+                }
+                Change::Locked(_) => {
+                    if let Some(placed) = placed {
+                        if Self::train(&placed, painter, state) {
+                            return false;
+                        }
+                    }
+                    if state.replay.lock().unwrap().is_none()
+                        && state.royale.lock().unwrap().is_none()
+                    {
+                        frame::FinesseFrame::draw_inner(painter, state);
+                    }
 
-        let block = lock.current_block_mut();
-        block.shift(&TO_DROP_POINT);
+                    // falling block is part of stacked blocks now, keep it on screen
+                    state.falling.lock().unwrap().take();
+                    frame::GameFrame::draw_stacked(painter, state);
+                    frame::RecordFrame::draw_inner(painter, state);
+                }
+                Change::Full(_) => {}
+                Change::Cleared(_) => {
+                    frame::RecordFrame::draw_inner(painter, state);
+                    frame::GameFrame::draw_inner(painter, state);
+                }
+                Change::Spawned => {
+                    frame::NextBlockFrame::reset_inner(painter, state);
+                    frame::NextBlockFrame::draw_inner(painter, state);
+                    falling = true;
+                }
+                Change::Held => {
+                    frame::HoldFrame::reset_inner(painter, state);
+                    frame::HoldFrame::draw_inner(painter, state);
+                }
+                Change::Second(_) => frame::RecordFrame::draw_inner(painter, state),
+                Change::Sent(lines) => {
+                    state.sent.fetch_add(lines, Ordering::Relaxed);
+                }
+                Change::Pending(_) => frame::GameFrame::draw_garbage(painter, state),
+                Change::Risen(_) => frame::GameFrame::draw_inner(painter, state),
+                Change::Over => over = true,
+            }
+        }
 
-        if Self::is_valid_position(block.points(), state) {
-            // likely
-            drop(lock);
+        if falling {
+            frame::GameFrame::reset_falling(painter, state);
+            frame::GameFrame::draw_hint(painter, state);
+            frame::GameFrame::draw_target(painter, state);
             frame::GameFrame::draw_falling(painter, state);
-        } else {
-            // unlikely
-            block.shift(&TO_RISE_POINT);
-            let color = block.color();
-
-            state
-                .stacked_blocks
-                .lock()
-                .unwrap()
-                .borrow_mut()
-                .cover(*color, block.points());
-
-            drop(lock);
-            frame::GameFrame::draw_stacked(painter, state);
-            Self::generate_new_block(painter, state);
-            Self::settle_up(painter, state);
         }
+        frame::GameFrame::draw_clearing(painter, state);
 
-        state.timer.lock_cond().0.lock().unwrap().set_now::<1>();
+        over
     }
 
-    fn translate(to: &block::Point, painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        let mut points = *state.two_blocks.lock().unwrap().current_block().points();
-        points.add_assign(to);
-
-        if Self::is_valid_position(&points, state) {
-            // in frame
-            frame::GameFrame::reset_falling(painter, state);
-
-            state
-                .two_blocks
-                .lock()
-                .unwrap()
-                .current_block_mut()
-                .shift(to);
+    /// next input of the bot, as if its key is pressed
+    pub fn autoplay(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        if !matches!(*state.screen.lock().unwrap(), menu::Screen::Game) {
+            return;
+        }
 
-            frame::GameFrame::draw_falling(painter, state);
+        let event = match &mut *state.autoplay.lock().unwrap() {
+            Some(autoplay) => autoplay.next(&state.engine.lock().unwrap()),
+            None => return,
+        };
+        match event {
+            Ok(Some(event)) => Self::apply(event, painter, state),
+            Ok(None) => {}
+            Err(e) => {
+                *state.message.lock().unwrap() = Some(format!("bot fails: {}", e));
+                Self::quit(painter, state);
+            }
         }
     }
 
-    fn left(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        Self::translate(&TO_LEFT_POINT, painter, state);
+    /// true if the trainer puts current block back to be placed again,
+    /// what came after it is thrown away
+    fn train(
+        placed: &finesse::Placed,
+        painter: &Arc<painter::Painter>,
+        state: &Arc<state::State>,
+    ) -> bool {
+        let mut guard = state.trainer.lock().unwrap();
+        let trainer = match &mut *guard {
+            Some(trainer) => trainer,
+            None => return false,
+        };
+
+        let retry = trainer.retry.take();
+        if trainer.judge(placed) {
+            return false;
+        }
+        let mut engine = match retry {
+            Some(engine) => engine,
+            None => return false,
+        };
+
+        engine.take_changes();
+        state
+            .finesse
+            .lock()
+            .unwrap()
+            .enter(engine.current_block().placement());
+        let kind = trainer.target.as_ref().map(|(target, _)| target.kind);
+        if kind != Some(engine.current_block().kind()) {
+            trainer.enter(engine.current_block().placement(), state.get_size().0);
+        }
+        *state.engine.lock().unwrap() = engine;
+        state.hinted.lock().unwrap().take();
+        drop(guard);
+
+        Self::draw_all(painter, state);
+        true
     }
 
-    fn right(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        Self::translate(&TO_RIGHT_POINT, painter, state);
+    fn record(time: Duration, event: event::Event, state: &Arc<state::State>) {
+        if let Some(recorder) = &mut *state.recorder.lock().unwrap() {
+            recorder.write(time, event);
+        }
     }
 
-    fn rotate(direction: isize, painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        frame::GameFrame::reset_falling(painter, state);
-        {
-            let mut lock = state.two_blocks.lock().unwrap();
-            let block = lock.current_block_mut();
+    fn quit(_: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        state.quit_signal.store(true, Ordering::Relaxed);
+    }
 
-            *block += direction;
-            if !Self::is_valid_position(block.points(), state) {
-                *block += -direction;
-            }
-        }
-        frame::GameFrame::draw_falling(painter, state);
+    /// show or hide where the bot would place current block
+    fn hint(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        state.hint.fetch_xor(true, Ordering::Relaxed);
+        frame::GameFrame::draw_inner(painter, state);
     }
 
-    fn clock_rotate(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        Self::rotate(1, painter, state);
+    fn pause(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        *state.screen.lock().unwrap() = menu::Screen::Pause;
+        frame::GameFrame::draw_pause(painter, state);
     }
 
-    fn inverse_rotate(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        Self::rotate(-1, painter, state);
+    fn resume(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        *state.screen.lock().unwrap() = menu::Screen::Game;
+        frame::GameFrame::draw_inner(painter, state);
     }
 
-    fn hard_drop(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        // ! danger of dead lock: two_blocks, stacked_blocks
-        // ! drop to avoid dead lock
-        frame::GameFrame::reset_falling(painter, state);
+    /// stay paused if the game can't be saved
+    fn save_and_quit(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        if state.trainer.lock().unwrap().is_some() {
+            *state.message.lock().unwrap() = Some(String::from("a training can't be saved"));
+            return;
+        }
+
+        let saved = save::write(&state.engine.lock().unwrap());
+        *state.message.lock().unwrap() = Some(match saved {
+            Ok(path) => {
+                // not over yet, no high score
+                *state.mode.lock().unwrap() = None;
+                Self::quit(painter, state);
+                format!("💾 game saved to {} (go on with --resume)", path.display())
+            }
+            Err(e) => format!("fail to save game: {}", e),
+        });
+    }
 
-        let mut lock = state.two_blocks.lock().unwrap();
-        let block = lock.current_block_mut();
+    fn open_key_menu(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        *state.screen.lock().unwrap() = menu::Screen::KeyMenu(Default::default());
+        frame::MenuFrame::draw(painter, state);
+    }
 
-        while {
-            block.shift(&TO_DROP_POINT);
-            Self::is_valid_position(block.points(), state)
-        } {}
-        block.shift(&TO_RISE_POINT);
-        drop(lock);
+    fn close_key_menu(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let config = state.config.lock().unwrap().clone();
+        if let Err(e) = config.save() {
+            *state.message.lock().unwrap() = Some(format!("fail to save key bindings: {}", e));
+        }
 
-        Self::drop(painter, state);
+        *state.screen.lock().unwrap() = menu::Screen::Pause;
+        Self::draw_all(painter, state);
     }
 
-    fn score_update(
-        line: u32,
-        score: u32,
+    fn key_menu(
+        mut menu: menu::KeyMenu,
+        event: Event,
         painter: &Arc<painter::Painter>,
         state: &Arc<state::State>,
     ) {
-        // ! drop to avoid dead lock
-        let mut lock = state.record.lock().unwrap();
-        lock.line += line;
-        lock.score += score;
-        drop(lock);
+        let key = match event {
+            Event::Key(key) => event::Key::from(key),
+            Event::Resize => return Self::resize(painter, state),
+            _ => return,
+        };
+        menu.refused = None;
+
+        if let Some(binding) = menu.waiting.take() {
+            let action = menu.action();
+            let mut config = state.config.lock().unwrap();
+            let mut keys = match binding {
+                menu::Binding::Replace => Vec::new(),
+                menu::Binding::Add => config.keymap.keys(action).to_vec(),
+            };
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+            if config.keymap.bind(action, keys).is_err() {
+                menu.refused = Some(action);
+            }
+        } else {
+            match key.code {
+                KeyCode::Up => menu.up(),
+                KeyCode::Down => menu.down(),
+                KeyCode::Enter => menu.waiting = Some(menu::Binding::Replace),
+                KeyCode::Tab => menu.waiting = Some(menu::Binding::Add),
+                KeyCode::Backspace | KeyCode::Delete => {
+                    let action = menu.action();
+                    let mut config = state.config.lock().unwrap();
+                    if config.keymap.bind(action, Vec::new()).is_err() {
+                        menu.refused = Some(action);
+                    }
+                }
+                KeyCode::Esc => return Self::close_key_menu(painter, state),
+                _ => return,
+            }
+        }
 
-        frame::RecordFrame::draw_inner(painter, state);
+        *state.screen.lock().unwrap() = menu::Screen::KeyMenu(menu);
+        frame::MenuFrame::draw_inner(painter, state);
     }
 
-    fn time_update(secs: u32, painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        state.record.lock().unwrap().secs = secs;
-        frame::RecordFrame::draw_time(painter, state);
+    fn input(raw: Event, painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let event = state.config.lock().unwrap().keymap.get(&raw);
+
+        let screen = *state.screen.lock().unwrap();
+        match screen {
+            menu::Screen::Game => Self::apply(event, painter, state),
+            menu::Screen::Pause => match (event, raw) {
+                (event::Event::Toggle, _) => Self::resume(painter, state),
+                (event::Event::Quit, _) => Self::quit(painter, state),
+                (event::Event::Resize, _) => Self::resize(painter, state),
+                // key bindings and saves are files of the server, not of a remote player
+                (_, Event::Key(key)) if key.code == KeyCode::Enter && !state.remote => {
+                    Self::open_key_menu(painter, state)
+                }
+                (_, Event::Key(key)) if key.code == KeyCode::Char('s') && !state.remote => {
+                    Self::save_and_quit(painter, state)
+                }
+                _ => {}
+            },
+            menu::Screen::KeyMenu(menu) => Self::key_menu(menu, raw, painter, state),
+        }
     }
+}
 
-    // fn speed_update(speed: u32, painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-    //     state.record.lock().unwrap().speed = speed;
-    //     frame::RecordFrame::draw_inner(painter, state);
-    // }
+impl Handler {
+    pub fn start(
+        &mut self,
+        painter: Arc<painter::Painter>,
+        state: Arc<state::State>,
+        event_rx: Receiver<Event>,
+    ) {
+        self.event_thread(event_rx, painter, state);
+    }
 
-    fn settle_up(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        let full_lines = state.stacked_blocks.lock().unwrap().full_lines();
-        if !full_lines.is_empty() {
-            Self::blink(&full_lines, Duration::from_millis(400), 2, painter, state);
+    pub fn start_replay(
+        &mut self,
+        painter: Arc<painter::Painter>,
+        state: Arc<state::State>,
+        replay: replay::Replay,
+        event_rx: Receiver<Event>,
+    ) {
+        let handler = thread::spawn(move || {
+            player::Player::new(replay).run(event_rx, &painter, &state);
+        });
 
-            let line = full_lines.len() as u32;
-            state.stacked_blocks.lock().unwrap().eliminate(&full_lines);
-            Self::score_update(line, line_to_score(line), painter, state);
-        }
-        frame::GameFrame::draw_inner(painter, state);
+        self.threads.push(handler);
     }
 
-    fn blink(
-        lines: &Vec<usize>,
-        duration: Duration,
-        times: i32,
-        painter: &Arc<painter::Painter>,
-        state: &Arc<state::State>,
+    /// `states` are played side by side, the first one on the left
+    pub fn start_versus(
+        &mut self,
+        painter: Arc<painter::Painter>,
+        states: Vec<Arc<state::State>>,
+        event_rx: Receiver<Event>,
     ) {
-        state.timer.pause();
-        state.handle_signal.store(false, Ordering::Relaxed);
+        let handler = thread::spawn(move || {
+            versus::Versus::new(states).run(event_rx, &painter);
+        });
 
-        for _ in 0..times {
-            frame::GameFrame::draw_blinking(painter, state, lines);
-            painter.flush().unwrap();
-            thread::sleep(duration);
-            frame::GameFrame::draw_stacked(painter, state);
-            painter.flush().unwrap();
-            thread::sleep(duration);
-        }
+        self.threads.push(handler);
+    }
 
-        state.handle_signal.store(true, Ordering::Release);
-        state.timer.resume();
+    /// the board of `state` in the middle of the bot boards of `royale`
+    pub fn start_royale(
+        &mut self,
+        painter: Arc<painter::Painter>,
+        state: Arc<state::State>,
+        royale: royale::Royale,
+        event_rx: Receiver<Event>,
+    ) {
+        let handler = thread::spawn(move || {
+            arena::Arena::new(state, royale).run(event_rx, &painter);
+        });
+
+        self.threads.push(handler);
     }
 
-    fn generate_new_block(painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
-        frame::NextBlockFrame::reset_inner(painter, state);
-
-        let x = rand::random::<usize>() % (block::BLOCKS.len() * block::ORIENTATION_COUNT);
-        let (b, o) = (x / block::ORIENTATION_COUNT, x % block::ORIENTATION_COUNT);
-        let block = Box::new(block::FallingBlock::new(b, o));
-        let _block = state.two_blocks.lock().unwrap().push(block);
-
-        let (col, row) = state.get_size();
-        let origin = block::Point::new(
-            (col - block::POINT_OF_BLOCK_COUNT) as isize / 2,
-            row as isize - 1,
-        );
-
-        let mut lock = state.two_blocks.lock().unwrap();
-        let block = lock.current_block_mut();
-        block.shift(&origin);
-
-        if {
-            let valid = Self::is_valid_position(block.points(), state);
-            drop(lock);
-            !valid
-        } {
-            *state.message.lock().unwrap() = Some(String::from("block stack overflow"));
-            Self::quit(painter, state);
+    /// the game as it is now to its viewers, if it is broadcast
+    fn broadcast(state: &Arc<state::State>, over: bool) {
+        if let Some(broadcast) = &mut *state.broadcast.lock().unwrap() {
+            let paused = !matches!(*state.screen.lock().unwrap(), menu::Screen::Game);
+            broadcast.update(&state.engine.lock().unwrap(), paused);
+            if over {
+                broadcast.send(&spectate::Message::Over);
+            }
         }
-
-        frame::GameFrame::draw_falling(painter, state);
-        frame::NextBlockFrame::draw_inner(painter, state);
     }
-}
 
-impl Handler {
-    pub fn start(
+    pub fn start_viewer(
         &mut self,
         painter: Arc<painter::Painter>,
         state: Arc<state::State>,
-        timer_rx: Receiver<u64>,
+        feed: spectate::Feed,
         event_rx: Receiver<Event>,
     ) {
-        self.time_update_thread(timer_rx, painter.clone(), state.clone());
-        self.event_thread(event_rx, painter.clone(), state.clone());
+        let handler = thread::spawn(move || {
+            viewer::Viewer::new(state, feed).run(event_rx, &painter);
+        });
+
+        self.threads.push(handler);
     }
 
-    fn time_update_thread(
+    pub fn start_netplay(
         &mut self,
-        timer_rx: Receiver<u64>,
         painter: Arc<painter::Painter>,
         state: Arc<state::State>,
+        mirror: Arc<state::State>,
+        peer: net::Peer,
+        event_rx: Receiver<Event>,
     ) {
         let handler = thread::spawn(move || {
-            while !state.quit() {
-                let secs = timer_rx.recv().unwrap() as u32;
-                Self::time_update(secs, &painter, &state);
-                painter.flush().unwrap();
-            }
+            netplay::Netplay::new(state, mirror, peer).run(event_rx, &painter);
         });
 
         self.threads.push(handler);
@@ -314,43 +480,43 @@ impl Handler {
         state: Arc<state::State>,
     ) {
         let handler = thread::spawn(move || {
+            state.engine.lock().unwrap().take_changes();
             Self::resize(&painter, &state);
-            Self::generate_new_block(&painter, &state);
-            frame::GameFrame::draw_inner(&painter, &state);
-
             painter.flush().unwrap();
 
+            let mut last = Instant::now();
+            let mut autoplay = last;
             while !state.quit() {
-                let event = event_rx.recv().unwrap();
-                if !state.handle_signal.load(Ordering::Relaxed) {
-                    continue;
+                let raw = match event_rx.recv_timeout(TICK) {
+                    Ok(raw) => Some(raw),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+
+                // whole milliseconds only, so recorded times play back exactly
+                let now = Instant::now();
+                if matches!(*state.screen.lock().unwrap(), menu::Screen::Game) {
+                    let millis = Duration::from_millis(now.duration_since(last).as_millis() as u64);
+                    last += millis;
+                    state.engine.lock().unwrap().tick(millis);
+                } else {
+                    last = now;
                 }
 
-                if state.timer.is_paused() {
-                    match event::Event::from(event) {
-                        event::Event::Toggle => Self::resume(&painter, &state),
-                        event::Event::Quit => Self::quit(&painter, &state),
-                        event::Event::Resize => Self::resize(&painter, &state),
-                        _ => {}
-                    }
-                    painter.flush().unwrap();
-
-                    continue;
+                if let Some(raw) = raw {
+                    Self::input(raw, &painter, &state);
                 }
-
-                match event::Event::from(event) {
-                    event::Event::Unknow => {}
-                    event::Event::Quit => Self::quit(&painter, &state),
-                    event::Event::Toggle => Self::pause(&painter, &state),
-                    event::Event::ClockRotate => Self::clock_rotate(&painter, &state),
-                    event::Event::InverseRotate => Self::inverse_rotate(&painter, &state),
-                    event::Event::Drop => Self::drop(&painter, &state),
-                    event::Event::Left => Self::left(&painter, &state),
-                    event::Event::Right => Self::right(&painter, &state),
-                    event::Event::HardDrop => Self::hard_drop(&painter, &state),
-                    event::Event::Resize => Self::resize(&painter, &state),
+                if now >= autoplay {
+                    autoplay = now + AUTOPLAY_INTERVAL;
+                    Self::autoplay(&painter, &state);
                 }
 
+                let over = Self::update(&painter, &state);
+                Self::broadcast(&state, over);
+                if over {
+                    *state.message.lock().unwrap() = Some(String::from("block stack overflow"));
+                    Self::quit(&painter, &state);
+                }
                 painter.flush().unwrap();
             }
         });
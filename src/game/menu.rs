@@ -0,0 +1,39 @@
+use super::event;
+
+/// what the event thread is showing and handling
+#[derive(Clone, Copy, Default)]
+pub enum Screen {
+    #[default]
+    Game,
+    Pause,
+    KeyMenu(KeyMenu),
+}
+
+/// how the next pressed key is used in key menu
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Replace,
+    Add,
+}
+
+/// key remapping menu, reachable from pause
+#[derive(Clone, Copy, Default)]
+pub struct KeyMenu {
+    pub cursor: usize,
+    pub waiting: Option<Binding>,
+    pub refused: Option<event::Event>, // action the last clear left without a key
+}
+
+impl KeyMenu {
+    pub fn action(&self) -> event::Event {
+        event::ACTIONS[self.cursor]
+    }
+
+    pub fn up(&mut self) {
+        self.cursor = (self.cursor + event::ACTIONS.len() - 1) % event::ACTIONS.len();
+    }
+
+    pub fn down(&mut self) {
+        self.cursor = (self.cursor + 1) % event::ACTIONS.len();
+    }
+}
@@ -0,0 +1,229 @@
+use super::block::{
+    FallingBlock, Placement, PointArray, StackedBlock, ORIENTATION_COUNT, POINT_OF_BLOCK_COUNT,
+};
+use super::event::Event;
+use super::finesse;
+
+/// inputs that move a block without locking it, soft drop included
+pub const INPUTS: [Event; 6] = [
+    Event::ClockRotate,
+    Event::InverseRotate,
+    Event::FlipRotate,
+    Event::Left,
+    Event::Right,
+    Event::Drop,
+];
+
+/// a position a block can lock at, with the fewest inputs to lock it there
+#[derive(Clone, Debug)]
+pub struct Lock {
+    pub placement: Placement,
+    pub points: PointArray, // sorted, the same for placements covering the same cells
+    pub inputs: Vec<Event>, // hard drop last
+}
+
+/// where a placement comes to rest when dropped straight down
+pub fn rest(stacked: &StackedBlock, placement: &Placement) -> Placement {
+    let mut placement = *placement;
+    loop {
+        let lower = finesse::moved(&placement, Event::Drop);
+        if !stacked.fits(&lower.points()) {
+            return placement;
+        }
+        placement = lower;
+    }
+}
+
+/// how far the points of a block reach from its offset
+const REACH: isize = POINT_OF_BLOCK_COUNT as isize - 1;
+
+/// placements a search has seen, a flag for each orientation and offset
+/// a block fitting on the board can be at, none above `top`
+struct Seen {
+    width: usize,
+    height: usize,
+    flags: Vec<bool>,
+}
+
+impl Seen {
+    fn new(column: usize, top: isize) -> Self {
+        let (width, height) = (column + REACH as usize, (top + REACH + 1) as usize);
+        Seen {
+            width,
+            height,
+            flags: vec![false; ORIENTATION_COUNT * width * height],
+        }
+    }
+
+    /// false if `placement` is seen already
+    fn insert(&mut self, placement: &Placement) -> bool {
+        let x = (placement.offset.x + REACH) as usize;
+        let y = (placement.offset.y + REACH) as usize;
+        let i = (placement.orientation * self.height + y) * self.width + x;
+        !std::mem::replace(&mut self.flags[i], true)
+    }
+}
+
+/// every distinct position `block` can lock at on `stacked`, tucks and spins included:
+/// a breadth first search over (x, y, rotation), each position it reaches is hard dropped
+pub fn generate(stacked: &StackedBlock, block: &FallingBlock) -> Vec<Lock> {
+    let from = block.placement();
+    if !stacked.fits(&from.points()) {
+        return Vec::new();
+    }
+
+    // blocks don't go up, so the search stays below where it starts
+    let mut seen = Seen::new(stacked.get_column(), from.offset.y);
+    seen.insert(&from);
+    // each placement reached, with the one it is reached from and the input to it,
+    // in the order of the search
+    let mut reached = vec![(from, 0, Event::Unknow)];
+    let mut locks: Vec<Lock> = Vec::new();
+
+    let mut i = 0;
+    while let Some(&(placement, _, input)) = reached.get(i) {
+        // reached with the fewest inputs, so is the lock under it if not locked yet,
+        // after a soft drop it is the lock under the placement above
+        if input != Event::Drop {
+            let at_rest = rest(stacked, &placement);
+            let mut points = at_rest.points();
+            points.sort_by_key(|p| (p.x, p.y));
+            if locks.iter().all(|lock| lock.points != points) {
+                locks.push(Lock {
+                    placement: at_rest,
+                    points,
+                    inputs: path(&reached, i),
+                });
+            }
+        }
+
+        for input in INPUTS {
+            let next = finesse::moved(&placement, input);
+            if stacked.fits(&next.points()) && seen.insert(&next) {
+                reached.push((next, i, input));
+            }
+        }
+        i += 1;
+    }
+
+    locks
+}
+
+/// inputs to the `i`th placement reached, and a hard drop
+fn path(reached: &[(Placement, usize, Event)], mut i: usize) -> Vec<Event> {
+    let mut inputs = vec![Event::HardDrop];
+    while i != 0 {
+        let (_, from, input) = reached[i];
+        inputs.push(input);
+        i = from;
+    }
+    inputs.reverse();
+    inputs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::block::{Point, BLOCKS};
+    use crate::game::bot::Bot;
+    use crate::game::engine::{Change, Engine, Rules};
+
+    /// a board 10 x 20 with the cells marked `x` covered, the last row on the floor
+    fn board(rows: &[&str]) -> StackedBlock {
+        let mut stacked = StackedBlock::new(10, 20);
+        for (y, row) in rows.iter().rev().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                if cell == 'x' {
+                    stacked.cover(*BLOCKS[0].color(), &[Point::new(x as isize, y as isize)]);
+                }
+            }
+        }
+        stacked
+    }
+
+    fn entered(kind: usize) -> FallingBlock {
+        Placement {
+            kind,
+            orientation: 0,
+            offset: Engine::new(Rules::new(42)).spawn_point(),
+        }
+        .block()
+    }
+
+    /// sorted points of `cells`, as `Lock` has them
+    fn points(cells: [(isize, isize); 4]) -> PointArray {
+        let mut points = cells.map(|(x, y)| Point::new(x, y));
+        points.sort_by_key(|p| (p.x, p.y));
+        points
+    }
+
+    #[test]
+    fn every_landing_on_an_empty_board() {
+        // j, l, s, z, i, t, o on a board 10 wide
+        let landings = [34, 34, 17, 17, 17, 34, 9];
+        let stacked = board(&[]);
+        for (kind, count) in landings.into_iter().enumerate() {
+            assert_eq!(
+                generate(&stacked, &entered(kind)).len(),
+                count,
+                "block {}",
+                kind
+            );
+        }
+    }
+
+    #[test]
+    fn tucks_under_an_overhang() {
+        let stacked = board(&["xxxx......", "..........", ".........."]);
+        let locks = generate(&stacked, &entered(6));
+        let tuck = locks
+            .iter()
+            .find(|lock| lock.points == points([(0, 0), (1, 0), (0, 1), (1, 1)]))
+            .unwrap();
+        assert!(tuck.inputs.contains(&Event::Drop));
+        assert_eq!(tuck.inputs[tuck.inputs.len() - 2], Event::Left);
+    }
+
+    #[test]
+    fn spins_into_a_t_slot() {
+        let stacked = board(&["xxx.......", "xx...xxxxx", "xxx.xxxxxx"]);
+        let locks = generate(&stacked, &entered(5));
+        let spin = locks
+            .iter()
+            .find(|lock| lock.points == points([(2, 1), (3, 0), (3, 1), (4, 1)]))
+            .unwrap();
+        assert!(spin.inputs.contains(&Event::Drop));
+        let turn = spin.inputs[spin.inputs.len() - 2];
+        assert!([Event::ClockRotate, Event::InverseRotate, Event::FlipRotate].contains(&turn));
+    }
+
+    #[test]
+    fn inputs_lock_where_found() {
+        let mut engine = Engine::new(Rules::new(42));
+        let bot = Bot::new(Default::default());
+        for _ in 0..30 {
+            for lock in generate(engine.stacked(), engine.current_block()) {
+                let mut tried = engine.clone();
+                for input in &lock.inputs {
+                    tried.apply(*input);
+                }
+                let locked = tried
+                    .take_changes()
+                    .into_iter()
+                    .find_map(|change| match change {
+                        Change::Locked(placement) => Some(placement),
+                        _ => None,
+                    });
+                let mut at = locked.unwrap().points();
+                at.sort_by_key(|p| (p.x, p.y));
+                assert_eq!(at, lock.points, "{:?}", lock.inputs);
+            }
+
+            for event in bot.think(&engine) {
+                engine.apply(event);
+            }
+            engine.tick(engine.rules().clear_delay);
+            engine.take_changes();
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use super::block::Placement;
+use super::engine::Engine;
+use super::garbage::Garbage;
+
+use serde::{Deserialize, Serialize};
+
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+/// version of the messages below, both sides must speak the same one
+pub const VERSION: u32 = 1;
+
+/// a peer that says nothing for this long is gone, pings keep a quiet one alive
+pub const TIMEOUT: Duration = Duration::from_secs(10);
+pub const PING_INTERVAL: Duration = Duration::from_secs(2);
+
+/// one line of json each way, `hello` first from both sides,
+/// then `start` from the host
+///
+/// ~~~text
+/// {"type":"hello","version":1,"name":"violet"}
+/// {"type":"start","seed":42,"garbage":{"lines":[0,1,2,4],...}}
+/// {"type":"lock","kind":5,"orientation":2,"x":3,"y":0}
+/// {"type":"sync","pieces":12,"board":1469598103934665603}
+/// {"type":"garbage","lines":4}
+/// ~~~
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    Hello {
+        version: u32,
+        name: String,
+    },
+    /// the blocks and the attack table both boards play by
+    Start {
+        seed: u64,
+        garbage: Garbage,
+    },
+    /// current block went to hold
+    Hold,
+    /// current block locked here
+    Lock {
+        kind: usize,
+        orientation: usize,
+        x: isize,
+        y: isize,
+    },
+    /// the board after the locks before it, by `board_hash`
+    Sync {
+        pieces: u32,
+        board: u64,
+    },
+    /// an attack on the board of the one it is sent to
+    Garbage {
+        lines: u32,
+    },
+    /// an attack was queued on the board of the sender
+    Received {
+        lines: u32,
+    },
+    /// the board of the sender topped out
+    Over,
+    /// the board of the receiver is not what the sender sees after `pieces`
+    Desync {
+        pieces: u32,
+    },
+    Ping,
+    Bye,
+}
+
+impl Message {
+    pub fn lock(placement: &Placement) -> Self {
+        Message::Lock {
+            kind: placement.kind,
+            orientation: placement.orientation,
+            x: placement.offset.x,
+            y: placement.offset.y,
+        }
+    }
+}
+
+/// the board of `engine`, full and empty lines left out so it is the same
+/// before and after they clear, cleared lines come back empty on top
+pub fn board_hash(engine: &Engine) -> u64 {
+    let stacked = engine.stacked();
+    // fnv-1a
+    stacked
+        .lines()
+        .iter()
+        .filter(|line| **line != 0 && **line != stacked.full())
+        .flat_map(|line| line.to_le_bytes())
+        .fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+/// the other side of a network game, its messages come in on a thread of their own
+pub struct Peer {
+    pub name: String,
+    stream: TcpStream,
+    message_rx: Receiver<Result<Message, String>>,
+}
+
+impl Peer {
+    /// wait on `addr` for one player to join, then tell them how to play
+    pub fn host(addr: &str, name: &str, seed: u64, garbage: &Garbage) -> Result<Self, String> {
+        let listener = TcpListener::bind(addr).map_err(|e| format!("{}: {}", addr, e))?;
+        let (stream, _) = listener.accept().map_err(|e| e.to_string())?;
+        let (mut stream, mut reader) = Self::open(stream)?;
+
+        send(&mut stream, &hello(name))?;
+        let peer = Self::hello(&mut reader)?;
+        send(
+            &mut stream,
+            &Message::Start {
+                seed,
+                garbage: garbage.clone(),
+            },
+        )?;
+        Ok(Self::spawn(peer, stream, reader))
+    }
+
+    /// join the game hosted at `addr`, with its seed and attack table
+    pub fn join(addr: &str, name: &str) -> Result<(Self, u64, Garbage), String> {
+        let stream = TcpStream::connect(addr).map_err(|e| format!("{}: {}", addr, e))?;
+        let (mut stream, mut reader) = Self::open(stream)?;
+
+        send(&mut stream, &hello(name))?;
+        let peer = Self::hello(&mut reader)?;
+        match receive(&mut reader)? {
+            Message::Start { seed, garbage } => {
+                Ok((Self::spawn(peer, stream, reader), seed, garbage))
+            }
+            message => Err(format!("expect start, got {:?}", message)),
+        }
+    }
+
+    fn open(stream: TcpStream) -> Result<(TcpStream, BufReader<TcpStream>), String> {
+        let error = |e: std::io::Error| e.to_string();
+        stream.set_nodelay(true).map_err(error)?;
+        stream.set_read_timeout(Some(TIMEOUT)).map_err(error)?;
+        let reader = BufReader::new(stream.try_clone().map_err(error)?);
+        Ok((stream, reader))
+    }
+
+    /// name of the peer, if it speaks our version
+    fn hello(reader: &mut BufReader<TcpStream>) -> Result<String, String> {
+        match receive(reader)? {
+            Message::Hello { version, name } if version == VERSION => Ok(name),
+            Message::Hello { version, .. } => Err(format!(
+                "peer speaks version {}, expect version {}",
+                version, VERSION
+            )),
+            message => Err(format!("expect hello, got {:?}", message)),
+        }
+    }
+
+    fn spawn(name: String, stream: TcpStream, mut reader: BufReader<TcpStream>) -> Self {
+        let (message_tx, message_rx) = mpsc::channel();
+        let who = name.clone();
+        thread::spawn(move || loop {
+            let message = receive(&mut reader).map_err(|e| format!("{}: {}", who, e));
+            let gone = message.is_err() || message == Ok(Message::Bye);
+            if message_tx.send(message).is_err() || gone {
+                break;
+            }
+        });
+
+        Peer {
+            name,
+            stream,
+            message_rx,
+        }
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), String> {
+        send(&mut self.stream, message).map_err(|e| format!("{}: {}", self.name, e))
+    }
+
+    /// next message, None if none came yet
+    pub fn try_recv(&self) -> Option<Result<Message, String>> {
+        match self.message_rx.try_recv() {
+            Ok(message) => Some(message),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(format!("{} is gone", self.name))),
+        }
+    }
+}
+
+fn hello(name: &str) -> Message {
+    Message::Hello {
+        version: VERSION,
+        name: String::from(name),
+    }
+}
+
+fn send(stream: &mut TcpStream, message: &Message) -> Result<(), String> {
+    let line = serde_json::to_string(message).map_err(|e| e.to_string())?;
+    writeln!(stream, "{}", line)
+        .and_then(|_| stream.flush())
+        .map_err(|e| e.to_string())
+}
+
+fn receive(reader: &mut BufReader<TcpStream>) -> Result<Message, String> {
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) => Err(String::from("connection closed")),
+        Ok(_) => {
+            serde_json::from_str(&line).map_err(|e| format!("bad message `{}`: {}", line.trim(), e))
+        }
+        Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+            Err(format!("no word for {} seconds", TIMEOUT.as_secs()))
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::bot::{Bot, Weights};
+    use crate::game::engine::Rules;
+    use crate::game::event::Event;
+
+    #[test]
+    fn board_hash_is_the_same_on_both_peers() {
+        let inputs = [
+            Event::Left,
+            Event::HardDrop,
+            Event::ClockRotate,
+            Event::Right,
+            Event::Right,
+            Event::HardDrop,
+            Event::Hold,
+            Event::HardDrop,
+        ];
+        let mut host = Engine::new(Rules::new(7));
+        let mut join = Engine::new(Rules::new(7));
+        for event in inputs {
+            host.apply(event);
+            join.apply(event);
+            assert_eq!(board_hash(&host), board_hash(&join));
+        }
+
+        join.apply(Event::HardDrop);
+        assert_ne!(board_hash(&host), board_hash(&join));
+    }
+
+    #[test]
+    fn board_hash_leaves_out_clearing_lines() {
+        // the bot plays until its first clear
+        let mut clearing = Engine::new(Rules::new(42));
+        let bot = Bot::new(Weights::default());
+        while clearing.clearing().is_none() {
+            assert!(!clearing.is_over());
+            for event in bot.think(&clearing) {
+                clearing.apply(event);
+            }
+        }
+
+        // one peer still shows the full lines, the other has cleared them
+        let mut cleared = clearing.clone();
+        cleared.tick(cleared.rules().clear_delay);
+        assert!(cleared.clearing().is_none());
+        assert_eq!(board_hash(&clearing), board_hash(&cleared));
+    }
+}
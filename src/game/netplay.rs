@@ -0,0 +1,256 @@
+use super::block::{Placement, Point};
+use super::bot;
+use super::engine::Change;
+use super::event::Event;
+use super::frame::{self, Frame};
+use super::handler::{self, Handler};
+use super::net::{self, Message, Peer};
+use super::{painter, state};
+
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// a versus game over the network: the board on the left is played here,
+/// the one on the right follows the holds and locks of the peer
+pub struct Netplay {
+    state: Arc<state::State>,
+    mirror: Arc<state::State>,
+    peer: Peer,
+    /// the last time anything was sent, a ping goes when it is too long ago
+    sent: Instant,
+}
+
+impl Netplay {
+    pub fn new(state: Arc<state::State>, mirror: Arc<state::State>, peer: Peer) -> Self {
+        *state.moves.lock().unwrap() = Some(Vec::new());
+        Netplay {
+            state,
+            mirror,
+            peer,
+            sent: Instant::now(),
+        }
+    }
+
+    pub fn run(mut self, event_rx: Receiver<terminal::Event>, painter: &Arc<painter::Painter>) {
+        for state in [&self.state, &self.mirror] {
+            state.engine.lock().unwrap().take_changes();
+        }
+        self.resize(painter);
+        painter.flush().unwrap();
+
+        let mut last = Instant::now();
+        while !self.state.quit() {
+            let raw = match event_rx.recv_timeout(handler::TICK) {
+                Ok(raw) => Some(raw),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            // no pause, the peer plays on anyway
+            let now = Instant::now();
+            let millis = Duration::from_millis(now.duration_since(last).as_millis() as u64);
+            last += millis;
+            for state in [&self.state, &self.mirror] {
+                state.engine.lock().unwrap().tick(millis);
+            }
+
+            if let Some(raw) = raw {
+                self.input(raw, painter);
+            }
+
+            let over = Handler::update(painter, &self.state);
+            self.send_moves();
+            let sent = self.state.sent.swap(0, Ordering::Relaxed);
+            if sent > 0 {
+                self.send(&Message::Garbage { lines: sent });
+            }
+            if over {
+                self.send(&Message::Over);
+                self.finish(&self.state, &self.mirror);
+            }
+
+            // nothing more is read once the game stops, a gone peer stays gone
+            while let Some(message) = self.peer.try_recv() {
+                self.receive(message, painter);
+                if self.state.quit() {
+                    break;
+                }
+            }
+            Handler::update(painter, &self.mirror);
+            // the attacks of the peer come from the peer
+            self.mirror.sent.store(0, Ordering::Relaxed);
+
+            if self.sent.elapsed() >= net::PING_INTERVAL {
+                self.send(&Message::Ping);
+            }
+            painter.flush().unwrap();
+        }
+        self.send(&Message::Bye);
+    }
+
+    fn send(&mut self, message: &Message) {
+        self.sent = Instant::now();
+        if let Err(e) = self.peer.send(message) {
+            self.stop(format!("🔌 {}", e));
+        }
+    }
+
+    /// holds and locks since the last time, then the board they leave
+    fn send_moves(&mut self) {
+        let moves = std::mem::take(self.state.moves.lock().unwrap().as_mut().unwrap());
+        if moves.is_empty() {
+            return;
+        }
+        for change in &moves {
+            match change {
+                Change::Held => self.send(&Message::Hold),
+                Change::Locked(placement) => self.send(&Message::lock(placement)),
+                _ => {}
+            }
+        }
+
+        let engine = self.state.engine.lock().unwrap();
+        let sync = Message::Sync {
+            pieces: engine.pieces(),
+            board: net::board_hash(&engine),
+        };
+        drop(engine);
+        self.send(&sync);
+    }
+
+    fn receive(&mut self, message: Result<Message, String>, painter: &Arc<painter::Painter>) {
+        let message = match message {
+            Ok(message) => message,
+            Err(e) => return self.stop(format!("🔌 {}", e)),
+        };
+
+        match message {
+            Message::Hold => {
+                self.catch_up();
+                self.mirror.engine.lock().unwrap().apply(Event::Hold);
+            }
+            Message::Lock {
+                kind,
+                orientation,
+                x,
+                y,
+            } => {
+                self.catch_up();
+                let mut engine = self.mirror.engine.lock().unwrap();
+                let target = Placement {
+                    kind,
+                    orientation,
+                    offset: Point::new(x, y),
+                };
+                let inputs = match kind == engine.current_block().kind() {
+                    true => bot::path(&engine, false, &target.points()),
+                    false => None,
+                };
+                match inputs {
+                    Some(inputs) => {
+                        for event in inputs {
+                            engine.apply(event);
+                        }
+                    }
+                    None => {
+                        let pieces = engine.pieces();
+                        drop(engine);
+                        self.desync(pieces);
+                    }
+                }
+            }
+            Message::Sync { pieces, board } => {
+                let engine = self.mirror.engine.lock().unwrap();
+                let synced = engine.pieces() == pieces && net::board_hash(&engine) == board;
+                drop(engine);
+                if !synced {
+                    self.desync(pieces);
+                }
+            }
+            Message::Garbage { lines } => {
+                self.state.engine.lock().unwrap().receive(lines);
+                self.send(&Message::Received { lines });
+            }
+            Message::Received { lines } => self.mirror.engine.lock().unwrap().receive(lines),
+            Message::Over => {
+                // the last locks of the peer are drawn before the game ends
+                Handler::update(painter, &self.mirror);
+                self.finish(&self.mirror, &self.state);
+            }
+            Message::Desync { pieces } => self.stop(format!(
+                "💥 {} sees another board here after {} piece(s)",
+                self.peer.name, pieces
+            )),
+            Message::Ping => {}
+            Message::Bye => self.stop(format!("🔌 {} left", self.peer.name)),
+            message => self.stop(format!(
+                "🔌 {}: unexpected message {:?}",
+                self.peer.name, message
+            )),
+        }
+    }
+
+    /// lines the peer saw cleared are cleared here too before its next move
+    fn catch_up(&self) {
+        let mut engine = self.mirror.engine.lock().unwrap();
+        if engine.clearing().is_some() {
+            let delay = engine.rules().clear_delay;
+            engine.tick(delay);
+        }
+    }
+
+    /// the board of the peer is not what the peer sees, the game can't go on
+    fn desync(&mut self, pieces: u32) {
+        self.send(&Message::Desync { pieces });
+        self.stop(format!(
+            "💥 the board of {} is out of sync after {} piece(s)",
+            self.peer.name, pieces
+        ));
+    }
+
+    /// `loser` topped out, the game ends
+    fn finish(&self, loser: &Arc<state::State>, winner: &Arc<state::State>) {
+        self.stop(format!(
+            "🏁 {} wins, {} topped out",
+            winner.seat.name, loser.seat.name
+        ));
+    }
+
+    fn stop(&self, message: String) {
+        let mut shown = self.state.message.lock().unwrap();
+        // the first reason is the one to show
+        if shown.is_none() {
+            *shown = Some(message);
+        }
+        self.state.quit_signal.store(true, Ordering::Relaxed);
+    }
+
+    fn resize(&self, painter: &Arc<painter::Painter>) {
+        frame::GameFrame::flush_terminal_size(painter);
+        if frame::GameFrame::test_terminal_size(&self.state).is_err() {
+            self.stop(String::from("terminal size is too small"));
+            return;
+        }
+
+        painter.clear_all().unwrap();
+        for state in [&self.state, &self.mirror] {
+            Handler::draw_frames(painter, state);
+        }
+    }
+
+    /// keys play the board here, pause is left out as the peer can't wait
+    fn input(&mut self, raw: terminal::Event, painter: &Arc<painter::Painter>) {
+        if let terminal::Event::Resize = raw {
+            return self.resize(painter);
+        }
+
+        let event = self.state.config.lock().unwrap().keymap.get(&raw);
+        match event {
+            Event::Toggle | Event::Hint => {}
+            Event::Quit => self.state.quit_signal.store(true, Ordering::Relaxed),
+            event => Handler::apply(event, painter, &self.state),
+        }
+    }
+}
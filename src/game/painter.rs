@@ -1,17 +1,21 @@
 use super::block;
+use super::telnet;
 
 use std::{
-    io::{Stdout, Write},
+    io::{self, BufWriter, Stdout, Write},
     sync::{Mutex, MutexGuard},
     time::Duration,
 };
 
+use crossterm::{cursor, style, terminal as screen, QueueableCommand};
 use terminal::{error::Result, Action, Color, Event, Retrieved, Terminal, Value};
 
-const EMPTY: &'static str = "  ";
-const BLOCK: &'static str = "██";
+const EMPTY: &str = "  ";
+const BLOCK: &str = "██";
+const GHOST: &str = "[]";
+const HINT: &str = "<>";
 
-const VERTICAL_BAR: char = '┃';
+pub const VERTICAL_BAR: char = '┃';
 // const HORIZONTAL_BAR: char = '━';
 const TOP_LEFT_CORNER: char = '┏';
 const TOP_RIGHT_CORNER: char = '┓';
@@ -22,8 +26,94 @@ fn horizontal_line(count: usize, front: char, back: char) -> String {
     format!("{}{:━^count$}{}", front, "", back)
 }
 
+/// where the painter draws
+enum Output {
+    /// the terminal the game runs in
+    Local(Terminal<Stdout>),
+    /// a terminal far away, drawn on through `sink`, its keys and size come from `keyboard`
+    Remote {
+        sink: BufWriter<Box<dyn Write + Send>>,
+        keyboard: telnet::Keyboard,
+    },
+}
+
+impl Output {
+    fn act(&mut self, action: Action) -> Result<()> {
+        self.batch(action)?;
+        self.flush_batch()
+    }
+
+    fn batch(&mut self, action: Action) -> Result<()> {
+        let sink = match self {
+            Output::Local(terminal) => return terminal.batch(action),
+            Output::Remote { sink, .. } => sink,
+        };
+        // raw mode and the mouse are up to the client, a gone one is noticed by its keyboard
+        let _ = match action {
+            Action::MoveCursorTo(col, row) => sink.queue(cursor::MoveTo(col, row)),
+            Action::HideCursor => sink.queue(cursor::Hide),
+            Action::ShowCursor => sink.queue(cursor::Show),
+            Action::ClearTerminal(clear) => sink.queue(screen::Clear(clear.into())),
+            Action::SetTerminalSize(col, row) => sink.queue(screen::SetSize(col, row)),
+            Action::EnterAlternateScreen => sink.queue(screen::EnterAlternateScreen),
+            Action::LeaveAlternateScreen => sink.queue(screen::LeaveAlternateScreen),
+            Action::SetForegroundColor(color) => {
+                sink.queue(style::SetForegroundColor(color.into()))
+            }
+            Action::ResetColor => sink.queue(style::ResetColor),
+            _ => Ok(sink),
+        };
+        Ok(())
+    }
+
+    fn flush_batch(&mut self) -> Result<()> {
+        match self {
+            Output::Local(terminal) => terminal.flush_batch(),
+            // drawn all at once on the next flush, not one move at a time
+            Output::Remote { .. } => Ok(()),
+        }
+    }
+
+    fn get(&self, value: Value) -> Result<Retrieved> {
+        let keyboard = match self {
+            Output::Local(terminal) => return terminal.get(value),
+            Output::Remote { keyboard, .. } => keyboard,
+        };
+        match value {
+            Value::TerminalSize => {
+                let (col, row) = keyboard.size();
+                Ok(Retrieved::TerminalSize(col, row))
+            }
+            Value::Event(_) => Ok(Retrieved::Event(keyboard.try_recv()?)),
+            Value::CursorPosition => Ok(Retrieved::CursorPosition(0, 0)),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Output::Local(terminal) => terminal.write(buf),
+            Output::Remote { sink, .. } => {
+                sink.write_all(buf).ok();
+                Ok(buf.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Output::Local(terminal) => terminal.flush(),
+            Output::Remote { sink, .. } => {
+                sink.flush().ok();
+                Ok(())
+            }
+        }
+    }
+}
+
 pub struct Painter {
-    stdout: Mutex<terminal::Terminal<Stdout>>,
+    output: Mutex<Output>,
     color: Mutex<Color>,
 }
 
@@ -35,16 +125,28 @@ impl Drop for Painter {
 
 impl Painter {
     pub fn new() -> Self {
+        Self::with(Output::Local(terminal::stdout()))
+    }
+
+    /// draw on a telnet client through `sink`, keys and size come from `keyboard`
+    pub fn remote(sink: Box<dyn Write + Send>, keyboard: telnet::Keyboard) -> Self {
+        Self::with(Output::Remote {
+            sink: BufWriter::new(sink),
+            keyboard,
+        })
+    }
+
+    fn with(output: Output) -> Self {
         let painter = Painter {
-            stdout: Mutex::new(terminal::stdout()),
+            output: Mutex::new(output),
             color: Mutex::new(Color::Reset),
         };
         painter.enter_terminal().unwrap();
         painter
     }
 
-    fn stdout_lock(&self) -> MutexGuard<'_, Terminal<Stdout>> {
-        self.stdout.lock().unwrap()
+    fn output_lock(&self) -> MutexGuard<'_, Output> {
+        self.output.lock().unwrap()
     }
 
     /// (top, bottom, left, right)
@@ -55,33 +157,31 @@ impl Painter {
         self.multiple_writeln_at(
             Color::Reset,
             (left, top),
-            std::iter::repeat(format!("{:col$}", "").as_bytes()).take(row),
+            std::iter::repeat_n(format!("{:col$}", "").as_bytes(), row),
         )?;
         Ok(())
     }
 
     // (col, row)
     pub fn resize(&self, size: (u16, u16)) -> Result<()> {
-        self.stdout_lock()
+        self.output_lock()
             .act(Action::SetTerminalSize(size.0, size.1))?;
         Ok(())
     }
 
     pub fn clear_all(&self) -> Result<()> {
-        self.stdout_lock()
+        self.output_lock()
             .act(Action::ClearTerminal(terminal::Clear::All))?;
         Ok(())
     }
 
     pub fn flush(&self) -> Result<()> {
-        self.stdout.lock().unwrap().flush()?;
+        self.output_lock().flush()?;
         Ok(())
     }
 
     pub fn get_size(&self) -> Result<(u16, u16)> {
-        if let Retrieved::TerminalSize(col, row) =
-            self.stdout.lock().unwrap().get(Value::TerminalSize)?
-        {
+        if let Retrieved::TerminalSize(col, row) = self.output_lock().get(Value::TerminalSize)? {
             Ok((col, row))
         } else {
             panic!("shouldn't reach here");
@@ -94,7 +194,7 @@ impl Painter {
     pub fn get_event(&self) -> Result<Option<Event>> {
         // Duration::from_secs(0) for non-blocking check
         if let Retrieved::Event(event) = self
-            .stdout_lock()
+            .output_lock()
             .get(Value::Event(Some(Duration::from_secs(0))))?
         {
             Ok(event)
@@ -107,7 +207,7 @@ impl Painter {
     }
 
     pub fn enter_terminal(&self) -> Result<()> {
-        let lock = self.stdout_lock();
+        let mut lock = self.output_lock();
         lock.batch(terminal::Action::EnterAlternateScreen)?;
         lock.batch(terminal::Action::HideCursor)?;
         lock.batch(terminal::Action::DisableMouseCapture)?;
@@ -119,7 +219,7 @@ impl Painter {
     }
 
     pub fn leave_terminal(&self) -> Result<()> {
-        let lock = self.stdout_lock();
+        let mut lock = self.output_lock();
 
         lock.batch(terminal::Action::LeaveAlternateScreen)?;
         lock.batch(terminal::Action::EnableMouseCapture)?;
@@ -194,6 +294,43 @@ impl Painter {
         Ok(())
     }
 
+    /// outline of blocks, where one would be placed
+    pub fn draw_multiple_ghost_at<'a>(
+        &self,
+        color: Color,
+        left_bottom: (u16, u16),
+        points: impl Iterator<Item = &'a block::Point>,
+    ) -> Result<()> {
+        self.draw_multiple_outline_at(color, left_bottom, points, GHOST)
+    }
+
+    /// outline of blocks where a hint says to place one, unlike a ghost
+    pub fn draw_multiple_hint_at<'a>(
+        &self,
+        color: Color,
+        left_bottom: (u16, u16),
+        points: impl Iterator<Item = &'a block::Point>,
+    ) -> Result<()> {
+        self.draw_multiple_outline_at(color, left_bottom, points, HINT)
+    }
+
+    fn draw_multiple_outline_at<'a>(
+        &self,
+        color: Color,
+        left_bottom: (u16, u16),
+        points: impl Iterator<Item = &'a block::Point>,
+        outline: &str,
+    ) -> Result<()> {
+        for block::Point { x, y } in points {
+            let pos = (
+                (left_bottom.0 as isize + x * 2) as u16,
+                (left_bottom.1 as isize - y) as u16,
+            );
+            self.write_at(color, pos, outline.as_bytes())?;
+        }
+        Ok(())
+    }
+
     // pub fn draw_block_at(
     //     &self,
     //     color: Color,
@@ -225,7 +362,7 @@ impl Painter {
     // }
 
     pub fn write_at(&self, color: Color, pos: (u16, u16), buf: &[u8]) -> Result<()> {
-        let mut lock = self.stdout_lock();
+        let mut lock = self.output_lock();
 
         let mut color_lock = self.color.lock().unwrap();
         if *color_lock != color {
@@ -243,9 +380,9 @@ impl Painter {
         &self,
         color: Color,
         mut pos: (u16, u16),
-        mut it: impl Iterator<Item = &'a [u8]>,
+        it: impl Iterator<Item = &'a [u8]>,
     ) -> Result<()> {
-        while let Some(buf) = it.next() {
+        for buf in it {
             self.write_at(color, pos, buf)?;
             pos.1 += 1;
         }
@@ -0,0 +1,217 @@
+use super::engine::Engine;
+use super::event;
+use super::frame::{self, Frame};
+use super::handler::{self, Handler};
+use super::{painter, replay, state};
+
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use terminal::{Event, KeyCode, KeyModifiers};
+
+/// plays a replay through engine, recorded inputs are applied at their times of play
+pub struct Player {
+    replay: replay::Replay,
+    index: usize, // next event to play
+    status: replay::Status,
+    shown: Option<(replay::Status, u32)>, // status and pieces on screen
+
+    // engine clock is brought up to date at `instant`
+    instant: Instant,
+}
+
+impl Player {
+    pub fn new(replay: replay::Replay) -> Self {
+        Player {
+            replay,
+            index: 0,
+            status: Default::default(),
+            shown: None,
+            instant: Instant::now(),
+        }
+    }
+
+    pub fn run(
+        mut self,
+        event_rx: Receiver<Event>,
+        painter: &Arc<painter::Painter>,
+        state: &Arc<state::State>,
+    ) {
+        state.engine.lock().unwrap().take_changes();
+        *state.replay.lock().unwrap() = Some(self.status);
+        Handler::resize(painter, state);
+        painter.flush().unwrap();
+
+        self.status.ended = self.is_done(&state.engine.lock().unwrap());
+        self.instant = Instant::now();
+
+        while !state.quit() {
+            let input = match event_rx.recv_timeout(handler::TICK) {
+                Ok(input) => Some(input),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            self.advance(state);
+            if let Some(event) = input {
+                self.control(event, painter, state);
+            }
+
+            // block stack overflow ends the game, but the viewer stays on it
+            Handler::update(painter, state);
+            if !self.status.ended && self.is_done(&state.engine.lock().unwrap()) {
+                self.finish(state);
+            }
+
+            let shown = Some((self.status, state.engine.lock().unwrap().pieces()));
+            if shown != self.shown {
+                self.shown = shown;
+                *state.replay.lock().unwrap() = Some(self.status);
+                frame::ReplayFrame::draw_inner(painter, state);
+            }
+            painter.flush().unwrap();
+        }
+    }
+
+    /// time of play where the replay stops
+    fn end_time(&self) -> Duration {
+        match (self.replay.end, self.replay.events.last()) {
+            (Some((time, _, _)), _) | (None, Some(&(time, _))) => time,
+            (None, None) => Duration::ZERO,
+        }
+    }
+
+    fn is_done(&self, engine: &Engine) -> bool {
+        engine.is_over()
+            || (self.index == self.replay.events.len() && engine.clock() >= self.end_time())
+    }
+
+    /// bring engine up to the time shown now
+    fn advance(&mut self, state: &Arc<state::State>) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.instant);
+        self.instant = now;
+
+        if self.status.paused || self.status.ended {
+            return;
+        }
+
+        let mut engine = state.engine.lock().unwrap();
+        let target = engine.clock() + elapsed.mul_f32(self.status.speed());
+        self.play_until(target.min(self.end_time()), &mut engine);
+    }
+
+    fn play_until(&mut self, target: Duration, engine: &mut Engine) {
+        while let Some(&(time, event)) = self.replay.events.get(self.index) {
+            if time > target {
+                break;
+            }
+            self.play(time, event, engine);
+        }
+        engine.tick(target.saturating_sub(engine.clock()));
+    }
+
+    fn play(&mut self, time: Duration, event: event::Event, engine: &mut Engine) {
+        self.index += 1;
+        engine.tick(time.saturating_sub(engine.clock()));
+
+        // gravity comes again from tick
+        if event != event::Event::Gravity {
+            engine.apply(event);
+        }
+    }
+
+    fn finish(&mut self, state: &Arc<state::State>) {
+        self.status.ended = true;
+
+        if let Some((_, line, score)) = self.replay.end {
+            let engine = state.engine.lock().unwrap();
+            let record = engine.record();
+            if self.index == self.replay.events.len()
+                && (record.line, record.score) != (line, score)
+            {
+                *state.message.lock().unwrap() = Some(format!(
+                    "replay out of sync: recorded {} line(s) and score {}, replayed {} and {}",
+                    line, score, record.line, record.score
+                ));
+            }
+        }
+    }
+
+    /// play until `pieces` blocks are locked, starting over if already past
+    fn seek(&mut self, pieces: u32, painter: &Arc<painter::Painter>, state: &Arc<state::State>) {
+        let mut engine = state.engine.lock().unwrap();
+
+        if engine.pieces() > pieces || pieces == 0 {
+            let garbage = engine.garbage().clone();
+            *engine = Engine::new(self.replay.rules);
+            engine.set_garbage(garbage);
+            self.index = 0;
+        }
+
+        while engine.pieces() < pieces && !engine.is_over() {
+            match self.replay.events.get(self.index) {
+                Some(&(time, event)) => self.play(time, event, &mut engine),
+                None => break,
+            }
+        }
+
+        engine.take_changes();
+        self.status.ended = self.is_done(&engine);
+        drop(engine);
+
+        self.status.paused = true;
+        *state.replay.lock().unwrap() = Some(self.status);
+        *state.message.lock().unwrap() = None;
+        Handler::draw_all(painter, state);
+    }
+
+    fn control(
+        &mut self,
+        event: Event,
+        painter: &Arc<painter::Painter>,
+        state: &Arc<state::State>,
+    ) {
+        let key = match event {
+            Event::Key(key) => key,
+            Event::Resize => return Handler::resize(painter, state),
+            _ => return,
+        };
+
+        let pieces = state.engine.lock().unwrap().pieces();
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                state.quit_signal.store(true, Ordering::Relaxed)
+            }
+            KeyCode::Char('q') | KeyCode::Esc => state.quit_signal.store(true, Ordering::Relaxed),
+            KeyCode::Char(' ') | KeyCode::Char('p') => self.status.paused = !self.status.paused,
+            KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Up => {
+                self.status.speed = (self.status.speed + 1).min(replay::SPEEDS.len() - 1)
+            }
+            KeyCode::Char('-') | KeyCode::Down => {
+                self.status.speed = self.status.speed.saturating_sub(1)
+            }
+            KeyCode::Char('n') | KeyCode::Right => self.seek(pieces + 1, painter, state),
+            KeyCode::Char('b') | KeyCode::Left => {
+                self.seek(pieces.saturating_sub(1), painter, state)
+            }
+            KeyCode::Char('r') | KeyCode::Home => self.seek(0, painter, state),
+            KeyCode::Char(c) if c.is_ascii_digit() => {
+                let digit = c.to_digit(10).unwrap();
+                let goto = self.status.goto.unwrap_or(0).saturating_mul(10);
+                self.status.goto = Some(goto.saturating_add(digit));
+            }
+            KeyCode::Backspace => {
+                self.status.goto = self.status.goto.map(|n| n / 10).filter(|n| *n != 0);
+            }
+            KeyCode::Enter | KeyCode::Char('g') => {
+                if let Some(piece) = self.status.goto.take() {
+                    self.seek(piece.saturating_sub(1), painter, state);
+                }
+            }
+            _ => {}
+        }
+    }
+}
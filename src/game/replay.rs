@@ -0,0 +1,335 @@
+use super::block;
+use super::engine::{Record, Rules};
+use super::event;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const REPLAY_VERSION: u32 = 2;
+
+/// writes the events applied by engine, one per line
+///
+/// ~~~text
+/// tetris-replay 2
+/// seed 42
+/// size 10 20
+/// speed 1
+/// drop-interval 2000
+/// clear-delay 1600
+/// 1520 left
+/// 2000 gravity
+/// 2310 hard_drop
+/// end 2310 1 1
+/// ~~~
+///
+/// times are milliseconds of play, pauses not counted, gravity is only
+/// written down for reading: engine drops blocks again by itself,
+/// `end` carries the play time and the final line count and score
+pub struct Recorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    error: Option<io::Error>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, rules: &Rules) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut recorder = Recorder {
+            path: path.to_path_buf(),
+            writer: BufWriter::new(file),
+            error: None,
+        };
+
+        let Rules {
+            seed,
+            size: (column, row),
+            speed,
+            drop_interval,
+            clear_delay,
+        } = *rules;
+        recorder.write_line(format_args!("tetris-replay {}", REPLAY_VERSION));
+        recorder.write_line(format_args!("seed {}", seed));
+        recorder.write_line(format_args!("size {} {}", column, row));
+        recorder.write_line(format_args!("speed {}", speed));
+        recorder.write_line(format_args!("drop-interval {}", drop_interval.as_millis()));
+        recorder.write_line(format_args!("clear-delay {}", clear_delay.as_millis()));
+
+        match recorder.error.take() {
+            Some(e) => Err(format!("{}: {}", path.display(), e)),
+            None => Ok(recorder),
+        }
+    }
+
+    fn write_line(&mut self, args: std::fmt::Arguments) {
+        if self.error.is_none() {
+            if let Err(e) = writeln!(self.writer, "{}", args) {
+                self.error = Some(e);
+            }
+        }
+    }
+
+    /// only events changing the game are kept
+    pub fn write(&mut self, time: Duration, event: event::Event) {
+        use event::Event::*;
+        if matches!(event, Unknow | Quit | Toggle | Resize) {
+            return;
+        }
+        self.write_line(format_args!("{} {}", time.as_millis(), event.name()));
+    }
+
+    /// returns where the replay is saved
+    pub fn finish(mut self, time: Duration, record: &Record) -> Result<PathBuf, String> {
+        self.write_line(format_args!(
+            "end {} {} {}",
+            time.as_millis(),
+            record.line,
+            record.score
+        ));
+        if self.error.is_none() {
+            if let Err(e) = self.writer.flush() {
+                self.error = Some(e);
+            }
+        }
+
+        match self.error {
+            Some(e) => Err(format!("{}: {}", self.path.display(), e)),
+            None => Ok(self.path),
+        }
+    }
+}
+
+/// a recorded game read back from file
+pub struct Replay {
+    pub rules: Rules,
+    pub events: Vec<(Duration, event::Event)>,
+    pub end: Option<(Duration, u32, u32)>, // (time, line, score)
+}
+
+impl Replay {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let s = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&s).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut lines = s.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+        let mut header = |name: &str| -> Result<Vec<u64>, String> {
+            let (i, line) = lines.next().ok_or_else(|| format!("missing `{}`", name))?;
+            let mut words = line.split_whitespace();
+            if words.next() != Some(name) {
+                return Err(format!("line {}: expect `{}`", i + 1, name));
+            }
+            words
+                .map(|w| {
+                    w.parse()
+                        .map_err(|_| format!("line {}: bad number `{}`", i + 1, w))
+                })
+                .collect()
+        };
+
+        let version = header("tetris-replay")?;
+        if version != [REPLAY_VERSION as u64] {
+            return Err(format!("unsupported replay version {:?}", version));
+        }
+        let (seed, size, speed, interval, delay) = (
+            header("seed")?,
+            header("size")?,
+            header("speed")?,
+            header("drop-interval")?,
+            header("clear-delay")?,
+        );
+        let (seed, size, speed, interval, delay) =
+            match (&seed[..], &size[..], &speed[..], &interval[..], &delay[..]) {
+                ([seed], [column, row], [speed], [interval], [delay]) => {
+                    (*seed, (*column, *row), *speed, *interval, *delay)
+                }
+                _ => return Err(String::from("bad replay header")),
+            };
+        let bad = |_| String::from("bad replay rules");
+        let (column, row) = (
+            usize::try_from(size.0).map_err(bad)?,
+            usize::try_from(size.1).map_err(bad)?,
+        );
+        let speed = u32::try_from(speed).map_err(bad)?;
+        if !(block::POINT_OF_BLOCK_COUNT..=block::MAX_COLUMN).contains(&column)
+            || !(1..=block::MAX_ROW).contains(&row)
+            || speed == 0
+            || interval == 0
+        {
+            return Err(String::from("bad replay rules"));
+        }
+
+        let rules = Rules {
+            seed,
+            size: (column, row),
+            speed,
+            drop_interval: Duration::from_millis(interval),
+            clear_delay: Duration::from_millis(delay),
+        };
+
+        let mut events = Vec::new();
+        let mut end = None;
+        for (i, line) in lines {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let number = |w: &str| -> Result<u64, String> {
+                w.parse()
+                    .map_err(|_| format!("line {}: bad number `{}`", i + 1, w))
+            };
+            let small = |w: &str| -> Result<u32, String> {
+                w.parse()
+                    .map_err(|_| format!("line {}: bad number `{}`", i + 1, w))
+            };
+
+            match words[..] {
+                ["end", time, line, score] => {
+                    end = Some((
+                        Duration::from_millis(number(time)?),
+                        small(line)?,
+                        small(score)?,
+                    ));
+                }
+                [time, name] => {
+                    let event = event::ACTIONS
+                        .iter()
+                        .chain([event::Event::Gravity].iter())
+                        .find(|e| e.name() == name)
+                        .ok_or_else(|| format!("line {}: unknown event `{}`", i + 1, name))?;
+                    events.push((Duration::from_millis(number(time)?), *event));
+                }
+                _ => return Err(format!("line {}: expect `<time> <event>`", i + 1)),
+            }
+        }
+
+        Ok(Replay { rules, events, end })
+    }
+}
+
+/// playback speeds of replay viewer
+pub(crate) const SPEEDS: [f32; 6] = [0.25, 0.5, 1.0, 2.0, 4.0, 8.0];
+
+/// what replay viewer shows besides the game
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Status {
+    pub paused: bool,
+    pub ended: bool,
+    pub speed: usize,      // index in SPEEDS
+    pub goto: Option<u32>, // piece number being typed
+}
+
+impl Default for Status {
+    fn default() -> Self {
+        Status {
+            paused: false,
+            ended: false,
+            speed: 2,
+            goto: None,
+        }
+    }
+}
+
+impl Status {
+    pub fn speed(&self) -> f32 {
+        SPEEDS[self.speed]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::bot::{Bot, Weights};
+    use crate::game::engine::{Change, Engine};
+    use crate::game::event::Event;
+
+    /// write down the inputs engine took since the last call
+    fn record(engine: &mut Engine, recorder: &mut Recorder) {
+        for change in engine.take_changes() {
+            if let Change::Applied(time, event) = change {
+                recorder.write(time, event);
+            }
+        }
+    }
+
+    #[test]
+    fn replay_plays_the_recorded_game() {
+        let rules = Rules::new(42);
+        let path = std::env::temp_dir().join(format!("tetris-replay-test-{}", std::process::id()));
+        let mut recorder = Recorder::create(&path, &rules).unwrap();
+
+        // the bot plays with gravity and clears going on between its inputs
+        let mut engine = Engine::new(rules);
+        let bot = Bot::new(Weights::default());
+        while !engine.is_over() && engine.pieces() < 60 {
+            for event in bot.think(&engine) {
+                engine.apply(event);
+                engine.tick(Duration::from_millis(450));
+                record(&mut engine, &mut recorder);
+            }
+        }
+        recorder.finish(engine.clock(), engine.record()).unwrap();
+        assert!(engine.record().line > 0);
+
+        let replay = Replay::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let replay = replay.unwrap();
+        let (end, line, score) = replay.end.unwrap();
+        assert_eq!(
+            (end, line, score),
+            (engine.clock(), engine.record().line, engine.record().score)
+        );
+
+        // as the replay viewer plays it, gravity comes again from tick
+        let mut played = Engine::new(replay.rules);
+        for (time, event) in replay.events {
+            played.tick(time.saturating_sub(played.clock()));
+            if event != Event::Gravity {
+                played.apply(event);
+            }
+        }
+        played.tick(end.saturating_sub(played.clock()));
+        assert_eq!(played.save(), engine.save());
+    }
+
+    #[test]
+    fn parse_checks_the_rules() {
+        let replay = "tetris-replay 2
+seed 42
+size 10 20
+speed 1
+drop-interval 2000
+clear-delay 1600
+1520 left
+2000 gravity
+2310 hard_drop
+end 2310 1 1
+";
+        let parsed = Replay::parse(replay).unwrap();
+        assert_eq!(parsed.rules.size, (10, 20));
+        assert_eq!(
+            parsed.events,
+            [
+                (Duration::from_millis(1520), Event::Left),
+                (Duration::from_millis(2000), Event::Gravity),
+                (Duration::from_millis(2310), Event::HardDrop),
+            ]
+        );
+        assert_eq!(parsed.end, Some((Duration::from_millis(2310), 1, 1)));
+
+        for bad in [
+            "size 40 20",
+            "size 10 0",
+            "size 10 4294967296",
+            "speed 0",
+            "drop-interval 0",
+        ] {
+            let name = bad.split(' ').next().unwrap();
+            let line = replay.lines().find(|l| l.starts_with(name)).unwrap();
+            assert!(
+                Replay::parse(&replay.replace(line, bad)).is_err(),
+                "{}",
+                bad
+            );
+        }
+    }
+}
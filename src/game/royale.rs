@@ -0,0 +1,362 @@
+use super::bot::{Autoplay, Rival, Strength, Weights};
+use super::engine::{Change, Engine, Rules};
+use super::garbage::Garbage;
+
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use std::time::Duration;
+
+/// bots at most, so 99 boards play with the player
+pub const MAX_BOTS: usize = 98;
+
+/// targets are picked again this often, random ones move on and the others follow the boards
+pub const RETARGET_INTERVAL: Duration = Duration::from_secs(2);
+
+/// badge points for each extra quarter of attack
+const BADGE_LEVELS: [u32; 4] = [2, 6, 14, 30];
+
+/// who the attacks of a board go to
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Targeting {
+    /// anyone, another one from time to time
+    #[default]
+    Random,
+    /// every board targeting this one, anyone if none does
+    Attackers,
+    /// the board closest to topping out
+    Kos,
+    /// the board with the most badge points
+    Badges,
+}
+
+pub const TARGETINGS: [Targeting; 4] = [
+    Targeting::Random,
+    Targeting::Attackers,
+    Targeting::Kos,
+    Targeting::Badges,
+];
+
+impl Targeting {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Targeting::Random => "random",
+            Targeting::Attackers => "attackers",
+            Targeting::Kos => "kos",
+            Targeting::Badges => "badges",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        TARGETINGS.iter().find(|t| t.name() == name).copied()
+    }
+}
+
+/// one board of a battle royale, the player's or a bot's
+#[derive(Clone, Debug)]
+pub struct Player {
+    pub name: String,
+    pub targeting: Targeting,
+    /// the board attacked next, None once nobody else is left
+    pub target: Option<usize>,
+    pub kos: u32,
+    /// a point for each knock out, and the points of the board knocked out
+    pub badges: u32,
+    /// place the board went out at, None while it plays
+    pub place: Option<usize>,
+    /// the last board that attacked this one, it gets the knock out
+    pub attacker: Option<usize>,
+}
+
+impl Player {
+    fn new(name: String, targeting: Targeting) -> Self {
+        Player {
+            name,
+            targeting,
+            target: None,
+            kos: 0,
+            badges: 0,
+            place: None,
+            attacker: None,
+        }
+    }
+
+    pub fn is_out(&self) -> bool {
+        self.place.is_some()
+    }
+
+    /// lines an attack of `lines` sends with the badges of this board
+    pub fn boost(&self, lines: u32) -> u32 {
+        let level = BADGE_LEVELS.iter().filter(|b| self.badges >= **b).count() as u32;
+        lines + lines * level / 4
+    }
+}
+
+/// a board the built-in bot plays
+pub struct Opponent {
+    pub engine: Engine,
+    autoplay: Autoplay,
+    /// changed since it was last drawn
+    pub dirty: bool,
+}
+
+/// what the player sees of the battle, beside their board
+#[derive(Clone, Debug, PartialEq)]
+pub struct Status {
+    pub targeting: Targeting,
+    /// name of the board attacked next
+    pub target: Option<String>,
+    /// boards targeting the player
+    pub attackers: usize,
+    pub kos: u32,
+    pub badges: u32,
+    pub alive: usize,
+    pub total: usize,
+}
+
+/// boards of a battle royale: the player is 0 and plays elsewhere, the bots come after
+/// and play here on the same clock, attacks go where each board targets,
+/// the last one standing wins
+pub struct Royale {
+    pub players: Vec<Player>,
+    /// bots, `opponents[i]` plays `players[i + 1]`
+    pub opponents: Vec<Opponent>,
+    /// boards in the order they went out
+    pub order: Vec<usize>,
+    /// time since targets were picked
+    retargeted: Duration,
+    rng: ChaCha8Rng,
+}
+
+impl Royale {
+    /// `bots` boards of `rules` against the player named `name`, bots pick their targeting at random
+    pub fn new(
+        name: String,
+        targeting: Targeting,
+        rules: Rules,
+        garbage: &Garbage,
+        bots: usize,
+        weights: Weights,
+        strength: Strength,
+    ) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(rules.seed);
+        rng.set_stream(3);
+
+        let mut players = vec![Player::new(name, targeting)];
+        let mut opponents = Vec::new();
+        for i in 1..=bots {
+            let targeting = *TARGETINGS.choose(&mut rng).unwrap();
+            players.push(Player::new(format!("bot {}", i), targeting));
+
+            let mut engine = Engine::new(rules);
+            engine.set_garbage(garbage.clone());
+            // same blocks for everyone, mistakes of their own
+            let rival = Rival::new(weights, strength, rules.seed.wrapping_add(i as u64));
+            let mut autoplay = Autoplay::new(Box::new(rival));
+            autoplay.pps = Some(strength.pps);
+            opponents.push(Opponent {
+                engine,
+                autoplay,
+                dirty: true,
+            });
+        }
+
+        let mut royale = Royale {
+            players,
+            opponents,
+            order: Vec::new(),
+            retargeted: Duration::ZERO,
+            rng,
+        };
+        let player = Engine::new(rules);
+        royale.retarget_all(&player);
+        royale
+    }
+
+    /// boards still playing
+    pub fn alive(&self) -> usize {
+        self.players.iter().filter(|p| !p.is_out()).count()
+    }
+
+    /// the player went out or is the last one standing
+    pub fn is_over(&self) -> bool {
+        self.players[0].is_out() || self.alive() == 1
+    }
+
+    /// pick targeting of the player, the target goes with it
+    pub fn set_targeting(&mut self, targeting: Targeting, player: &Engine) {
+        self.players[0].targeting = targeting;
+        self.retarget(0, player);
+        self.touch();
+    }
+
+    /// move every bot board on by `dt`, each bot plays its next input if `think`
+    pub fn tick(&mut self, dt: Duration, think: bool, player: &Engine) -> Result<(), String> {
+        for (opponent, bot) in self.opponents.iter_mut().zip(&self.players[1..]) {
+            if bot.is_out() {
+                continue;
+            }
+            opponent.engine.tick(dt);
+            if think {
+                if let Some(event) = opponent.autoplay.next(&opponent.engine)? {
+                    opponent.engine.apply(event);
+                }
+            }
+        }
+
+        self.retargeted += dt;
+        if self.retargeted >= RETARGET_INTERVAL {
+            self.retargeted = Duration::ZERO;
+            self.retarget_all(player);
+            self.touch();
+        }
+        Ok(())
+    }
+
+    /// pass on the attacks since the last time, `sent` lines of them by the player,
+    /// and knock out the boards that topped out, `over` for the player;
+    /// the boards knocked out are returned
+    pub fn update(&mut self, player: &mut Engine, sent: u32, over: bool) -> Vec<usize> {
+        let mut attacks = vec![(0, sent)];
+        let mut out = Vec::new();
+        if over {
+            out.push(0);
+        }
+
+        for (i, opponent) in self.opponents.iter_mut().enumerate() {
+            if self.players[i + 1].is_out() {
+                continue;
+            }
+            for change in opponent.engine.take_changes() {
+                opponent.dirty = true;
+                match change {
+                    Change::Entered(_) => opponent.autoplay.plan = None,
+                    Change::Sent(lines) => attacks.push((i + 1, lines)),
+                    Change::Over => out.push(i + 1),
+                    _ => {}
+                }
+            }
+        }
+
+        for (from, lines) in attacks {
+            if lines > 0 && !self.players[from].is_out() {
+                self.attack(from, lines, player);
+            }
+        }
+        for i in &out {
+            self.knock_out(*i, player);
+        }
+        out
+    }
+
+    /// `lines` from board `from` to whoever it targets
+    fn attack(&mut self, from: usize, lines: u32, player: &mut Engine) {
+        let lines = self.players[from].boost(lines);
+        let mut targets: Vec<usize> = match self.players[from].targeting {
+            Targeting::Attackers => self.attackers(from).collect(),
+            _ => Vec::new(),
+        };
+        if targets.is_empty() {
+            targets.extend(self.players[from].target);
+        }
+
+        for to in targets {
+            match to {
+                0 => player.receive(lines),
+                _ => self.opponents[to - 1].engine.receive(lines),
+            }
+            self.players[to].attacker = Some(from);
+        }
+    }
+
+    /// board `i` topped out, the last one to attack it gets the knock out and its badges
+    fn knock_out(&mut self, i: usize, player: &Engine) {
+        self.players[i].place = Some(self.alive());
+        self.order.push(i);
+
+        if let Some(by) = self.players[i]
+            .attacker
+            .filter(|by| !self.players[*by].is_out())
+        {
+            self.players[by].kos += 1;
+            self.players[by].badges += 1 + self.players[i].badges;
+        }
+
+        for j in 0..self.players.len() {
+            if self.players[j].target == Some(i) && !self.players[j].is_out() {
+                self.retarget(j, player);
+            }
+        }
+        self.touch();
+    }
+
+    /// boards still playing that target board `i`
+    pub fn attackers(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        self.players
+            .iter()
+            .enumerate()
+            .filter(move |(_, p)| p.target == Some(i) && !p.is_out())
+            .map(|(j, _)| j)
+    }
+
+    fn retarget_all(&mut self, player: &Engine) {
+        for i in 0..self.players.len() {
+            if !self.players[i].is_out() {
+                self.retarget(i, player);
+            }
+        }
+    }
+
+    /// pick the target of board `i` by its targeting
+    fn retarget(&mut self, i: usize, player: &Engine) {
+        let others: Vec<usize> = (0..self.players.len())
+            .filter(|j| *j != i && !self.players[*j].is_out())
+            .collect();
+        let danger = |j: usize| match j {
+            0 => danger(player),
+            _ => danger(&self.opponents[j - 1].engine),
+        };
+
+        let target = match self.players[i].targeting {
+            Targeting::Random | Targeting::Attackers => others.choose(&mut self.rng).copied(),
+            Targeting::Kos => others.iter().copied().max_by_key(|j| danger(*j)),
+            Targeting::Badges => others
+                .iter()
+                .copied()
+                .max_by_key(|j| (self.players[*j].badges, danger(*j))),
+        };
+        self.players[i].target = target;
+    }
+
+    /// every bot board is drawn again, who targets whom may have changed
+    fn touch(&mut self) {
+        for opponent in &mut self.opponents {
+            opponent.dirty = true;
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        let player = &self.players[0];
+        Status {
+            targeting: player.targeting,
+            target: player.target.map(|t| self.players[t].name.clone()),
+            attackers: self.attackers(0).count(),
+            kos: player.kos,
+            badges: player.badges,
+            alive: self.alive(),
+            total: self.players.len(),
+        }
+    }
+}
+
+/// how close a board is to topping out: its height and the garbage about to rise
+fn danger(engine: &Engine) -> usize {
+    let height = engine
+        .stacked()
+        .lines()
+        .iter()
+        .rposition(|line| *line != 0)
+        .map_or(0, |y| y + 1);
+    height + engine.pending_garbage() as usize
+}
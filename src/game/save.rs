@@ -0,0 +1,44 @@
+use super::config::Config;
+use super::engine::Engine;
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// where save & quit puts the game, `$XDG_DATA_HOME/tetris/save`
+pub fn path() -> Option<PathBuf> {
+    Config::data_dir().map(|dir| dir.join("save"))
+}
+
+/// returns where the game is saved
+pub fn write(engine: &Engine) -> Result<PathBuf, String> {
+    let path = path().ok_or_else(|| String::from("no data directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+    }
+    fs::write(&path, engine.save()).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// read the saved game, it stays until `remove` once the game goes on
+pub fn load() -> Result<Engine, String> {
+    let path = path().ok_or_else(|| String::from("no data directory"))?;
+    let s = match fs::read_to_string(&path) {
+        Ok(s) => s,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            return Err(String::from("no saved game, save one with `s` in pause"))
+        }
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+
+    Engine::restore(&s).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// a game is resumed only once
+pub fn remove() -> Result<(), String> {
+    let path = path().ok_or_else(|| String::from("no data directory"))?;
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(format!("{}: {}", path.display(), e)),
+        _ => Ok(()),
+    }
+}
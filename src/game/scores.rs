@@ -0,0 +1,397 @@
+use super::config::Config;
+
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// scores kept in each table
+pub const TOP_COUNT: usize = 10;
+
+pub const MARATHON: &str = "marathon";
+
+/// one finished game
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Score {
+    pub mode: String,
+    pub size: (usize, usize), // (column, row)
+    pub score: u32,
+    pub line: u32,
+    pub level: u32,
+    pub secs: u32,
+    pub date: u64, // seconds since unix epoch
+    pub name: String,
+}
+
+impl Score {
+    /// a score of now
+    pub fn new(mode: &str, size: (usize, usize), name: String) -> Self {
+        Score {
+            mode: String::from(mode),
+            size,
+            score: 0,
+            line: 0,
+            level: 1,
+            secs: 0,
+            date: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            name,
+        }
+    }
+
+    fn same_table(&self, other: &Score) -> bool {
+        self.mode == other.mode && self.size == other.size
+    }
+
+    fn same_player(&self, other: &Score) -> bool {
+        self.same_table(other) && self.name == other.name
+    }
+
+    fn line(&self) -> String {
+        format!(
+            "{} {} {} {} {} {} {} {} {}",
+            self.mode,
+            self.size.0,
+            self.size.1,
+            self.score,
+            self.line,
+            self.level,
+            self.secs,
+            self.date,
+            self.name
+        )
+    }
+}
+
+/// where a score goes in its table
+pub struct Rank {
+    pub place: Option<usize>, // from 1, None out of the table
+    pub personal_best: bool,
+}
+
+/// high score tables per mode and board size, `$XDG_DATA_HOME/tetris/scores`
+///
+/// ~~~text
+/// # mode columns rows score lines level seconds date name
+/// marathon 10 20 120 15 1 311 1760000000 violet
+/// best marathon 10 20 140 18 2 350 1760000000 violet
+/// ~~~
+///
+/// `best` lines keep the best of each name in a table, placed or not
+#[derive(Default)]
+pub struct Scores {
+    scores: Vec<Score>, // each table from the best
+    bests: Vec<Score>,  // one per mode, size and name, in that order
+}
+
+impl Scores {
+    pub fn path() -> Option<PathBuf> {
+        Config::data_dir().map(|dir| dir.join("scores"))
+    }
+
+    /// missing file gives empty tables
+    pub fn load() -> Result<Self, String> {
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return Ok(Default::default()),
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(s) => Self::parse(&s).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Default::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or_else(|| String::from("no data directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+
+        fs::write(&path, self.text()).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// the file as `parse` reads it
+    fn text(&self) -> String {
+        let mut s = String::from("# mode columns rows score lines level seconds date name\n");
+        for score in &self.scores {
+            s += &format!("{}\n", score.line());
+        }
+        for score in &self.bests {
+            s += &format!("best {}\n", score.line());
+        }
+        s
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut scores = Scores::default();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (best, line) = match line.strip_prefix("best ") {
+                Some(line) => (true, line),
+                None => (false, line),
+            };
+            let words: Vec<&str> = line.splitn(9, ' ').collect();
+            let number = |w: &str| -> Result<u64, String> {
+                w.parse()
+                    .map_err(|_| format!("line {}: bad number `{}`", i + 1, w))
+            };
+            match words[..] {
+                [mode, column, row, score, line, level, secs, date, name] => {
+                    let score = Score {
+                        mode: String::from(mode),
+                        size: (number(column)? as usize, number(row)? as usize),
+                        score: number(score)? as u32,
+                        line: number(line)? as u32,
+                        level: number(level)? as u32,
+                        secs: number(secs)? as u32,
+                        date: number(date)?,
+                        name: String::from(name.trim()),
+                    };
+                    // files without `best` lines get them from the tables
+                    scores.keep_best(&score);
+                    if !best {
+                        scores.scores.push(score);
+                    }
+                }
+                _ => {
+                    return Err(format!(
+                        "line {}: expect `mode columns rows score lines level seconds date name`",
+                        i + 1
+                    ))
+                }
+            }
+        }
+
+        // a stable sort keeps the older one first on ties
+        scores.scores.sort_by_key(|s| std::cmp::Reverse(s.score));
+        Ok(scores)
+    }
+
+    /// keep `score` if it is the best of its name in its table, true if so
+    fn keep_best(&mut self, score: &Score) -> bool {
+        match self.bests.iter_mut().find(|s| s.same_player(score)) {
+            Some(best) if best.score >= score.score => false,
+            Some(best) => {
+                *best = score.clone();
+                true
+            }
+            None => {
+                self.bests.push(score.clone());
+                self.bests
+                    .sort_by(|a, b| (&a.mode, a.size, &a.name).cmp(&(&b.mode, b.size, &b.name)));
+                true
+            }
+        }
+    }
+
+    /// add a score and keep the best of its table, and the best of its name
+    pub fn add(&mut self, score: Score) -> Rank {
+        let personal_best = self.keep_best(&score);
+
+        let place = self
+            .scores
+            .iter()
+            .filter(|s| s.same_table(&score) && s.score >= score.score)
+            .count();
+        if place >= TOP_COUNT {
+            return Rank {
+                place: None,
+                personal_best,
+            };
+        }
+
+        let index = self
+            .scores
+            .iter()
+            .position(|s| s.score < score.score)
+            .unwrap_or(self.scores.len());
+        self.scores.insert(index, score.clone());
+
+        // drop the last one of a full table
+        let mut count = 0;
+        self.scores.retain(|s| {
+            if s.same_table(&score) {
+                count += 1;
+                count <= TOP_COUNT
+            } else {
+                true
+            }
+        });
+
+        Rank {
+            place: Some(place + 1),
+            personal_best,
+        }
+    }
+
+    /// load, add and save, for the end of a game
+    pub fn submit(score: Score) -> Result<Rank, String> {
+        let mut scores = Self::load()?;
+        let rank = scores.add(score);
+        if rank.place.is_some() || rank.personal_best {
+            scores.save()?;
+        }
+        Ok(rank)
+    }
+}
+
+impl fmt::Display for Scores {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.scores.is_empty() {
+            return write!(f, "no scores yet");
+        }
+
+        let mut tables: Vec<(&str, (usize, usize))> = Vec::new();
+        for score in &self.scores {
+            if !tables.contains(&(&score.mode, score.size)) {
+                tables.push((&score.mode, score.size));
+            }
+        }
+        tables.sort();
+
+        for (i, (mode, size)) in tables.into_iter().enumerate() {
+            if i != 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "{} {}x{}", mode, size.0, size.1)?;
+            writeln!(
+                f,
+                "  {:>2}  {:<16} {:>8} {:>6} {:>6} {:>9}  date",
+                "#", "name", "score", "lines", "level", "time"
+            )?;
+
+            let table = self
+                .scores
+                .iter()
+                .filter(|s| s.mode == mode && s.size == size);
+            for (place, s) in table.enumerate() {
+                writeln!(
+                    f,
+                    "  {:>2}  {:<16} {:>8} {:>6} {:>6} {:>9}  {}",
+                    place + 1,
+                    s.name,
+                    s.score,
+                    s.line,
+                    s.level,
+                    secs_to_string(s.secs),
+                    date_to_string(s.date)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn secs_to_string(secs: u32) -> String {
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// `yyyy-mm-dd` in utc
+fn date_to_string(secs: u64) -> String {
+    // days to civil date, from howard hinnant's date algorithms
+    let z = (secs / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(name: &str, score: u32) -> Score {
+        Score {
+            score,
+            ..Score::new(MARATHON, (10, 20), String::from(name))
+        }
+    }
+
+    #[test]
+    fn file_reads_back() {
+        let mut scores = Scores::default();
+        scores.add(score("violet", 120));
+        scores.add(score("two words", 300));
+        scores.add(Score {
+            size: (12, 24),
+            ..score("violet", 80)
+        });
+
+        let read = Scores::parse(&scores.text()).unwrap();
+        assert_eq!(read.scores, scores.scores);
+        assert_eq!(read.bests, scores.bests);
+        assert_eq!(read.scores[0].name, "two words");
+    }
+
+    #[test]
+    fn bad_lines_are_refused() {
+        assert!(Scores::parse("marathon 10 20 x 1 1 1 1 violet").is_err());
+        assert!(Scores::parse("marathon 10 20 120").is_err());
+        assert!(Scores::parse("best marathon 10 20 120").is_err());
+        assert!(Scores::parse("# comment\n\nmarathon 10 20 120 1 1 1 1 violet").is_ok());
+    }
+
+    #[test]
+    fn tables_keep_their_best() {
+        let mut scores = Scores::default();
+        for i in 1..=TOP_COUNT as u32 {
+            assert!(scores.add(score("violet", 10 * i)).place.is_some());
+        }
+        // a table of another size is kept apart
+        let other = scores.add(Score {
+            size: (12, 24),
+            ..score("violet", 1)
+        });
+        assert_eq!(other.place, Some(1));
+
+        assert!(scores.add(score("violet", 5)).place.is_none());
+        // a tie goes after the older one
+        let rank = scores.add(score("indigo", 50));
+        assert_eq!(rank.place, Some(7));
+        assert_eq!(scores.scores.len(), TOP_COUNT + 1);
+        assert!(scores.scores.iter().all(|s| s.score != 10));
+    }
+
+    #[test]
+    fn personal_bests_outside_the_table() {
+        let mut scores = Scores::default();
+        for _ in 0..TOP_COUNT {
+            scores.add(score("violet", 100));
+        }
+
+        let first = scores.add(score("indigo", 10));
+        assert_eq!((first.place, first.personal_best), (None, true));
+        let worse = scores.add(score("indigo", 5));
+        assert_eq!((worse.place, worse.personal_best), (None, false));
+        let better = scores.add(score("indigo", 20));
+        assert_eq!((better.place, better.personal_best), (None, true));
+
+        // the best stays in the file, and a file of tables only gets its bests from them
+        let mut read = Scores::parse(&scores.text()).unwrap();
+        assert!(!read.add(score("indigo", 15)).personal_best);
+        let mut old = Scores::parse("marathon 10 20 120 1 1 1 1 violet").unwrap();
+        assert!(!old.add(score("violet", 110)).personal_best);
+    }
+
+    #[test]
+    fn dates_in_utc() {
+        assert_eq!(date_to_string(0), "1970-01-01");
+        assert_eq!(date_to_string(951782400), "2000-02-29");
+        assert_eq!(date_to_string(1760000000), "2025-10-09");
+        assert_eq!(secs_to_string(3723), "01:02:03");
+    }
+}
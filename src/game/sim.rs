@@ -0,0 +1,184 @@
+use super::bot::Brain;
+use super::engine::{Engine, Record, Rules};
+use super::event::Event;
+
+use std::fmt;
+use std::ops::Range;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+/// time each input takes, one frame at 60 hz, gravity goes on meanwhile
+pub const INPUT_TIME: Duration = Duration::from_micros(16_667);
+
+/// a game a brain played without terminal, inputs go in one frame apart as soon as it thinks
+#[derive(Clone, Copy, Debug)]
+pub struct Played {
+    pub seed: u64,
+    pub record: Record,
+    pub over: bool, // topped out, false if stopped at the piece limit
+}
+
+/// play a game of `rules` until it is over or `max_pieces` are locked
+pub fn play(rules: Rules, brain: &mut dyn Brain, max_pieces: u32) -> Result<Played, String> {
+    let mut engine = Engine::new(rules);
+
+    while !engine.is_over() && engine.pieces() < max_pieces {
+        let pieces = engine.pieces();
+        for event in brain.think(&engine)? {
+            engine.tick(INPUT_TIME);
+            // gravity locked the block before the brain was done with it
+            if engine.pieces() != pieces || engine.is_over() {
+                break;
+            }
+            engine.apply(event);
+        }
+        // a brain that doesn't lock its block loses it where it is
+        if engine.pieces() == pieces && !engine.is_over() {
+            engine.apply(Event::HardDrop);
+        }
+        // full lines are removed once their delay passes
+        if let Some((_, shown)) = engine.clearing() {
+            let left = rules.clear_delay.saturating_sub(shown);
+            engine.tick(left);
+        }
+        engine.take_changes();
+    }
+
+    Ok(Played {
+        seed: rules.seed,
+        record: *engine.record(),
+        over: engine.is_over(),
+    })
+}
+
+/// one game for each seed of `seeds`, spread over `threads`,
+/// each thread asks `brain` for a brain of its own
+pub fn run<F>(
+    rules: Rules,
+    seeds: Range<u64>,
+    max_pieces: u32,
+    threads: usize,
+    brain: F,
+) -> Result<Vec<Played>, String>
+where
+    F: Fn() -> Result<Box<dyn Brain>, String> + Sync,
+{
+    let seeds = Mutex::new(seeds);
+    let played = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|_| {
+                scope.spawn(|| -> Result<(), String> {
+                    let mut brain = brain()?;
+                    loop {
+                        // the lock is let go before the game is played
+                        let seed = seeds.lock().unwrap().next();
+                        let Some(seed) = seed else { break };
+                        let game = play(Rules { seed, ..rules }, brain.as_mut(), max_pieces)?;
+                        played.lock().unwrap().push(game);
+                    }
+                    Ok(())
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .try_for_each(|worker| worker.join().unwrap())
+    })?;
+
+    let mut played = played.into_inner().unwrap();
+    played.sort_by_key(|game| game.seed);
+    Ok(played)
+}
+
+/// aggregate statistics of games
+#[derive(Clone, Debug, Default)]
+pub struct Summary {
+    pub games: usize,
+    pub mean_lines: f64,
+    pub mean_pieces: f64,
+    pub mean_score: f64,
+    pub scores: [u32; 5], // least, quartiles and most
+    pub top_outs: usize,
+}
+
+impl Summary {
+    pub fn new(played: &[Played]) -> Self {
+        if played.is_empty() {
+            return Summary::default();
+        }
+
+        let games = played.len();
+        let mean = |f: fn(&Played) -> u32| {
+            played.iter().map(|game| f(game) as f64).sum::<f64>() / games as f64
+        };
+
+        let mut scores: Vec<u32> = played.iter().map(|game| game.record.score).collect();
+        scores.sort_unstable();
+        let quantile = |q: usize| scores[(games - 1) * q / 4];
+
+        Summary {
+            games,
+            mean_lines: mean(|game| game.record.line),
+            mean_pieces: mean(|game| game.record.pieces),
+            mean_score: mean(|game| game.record.score),
+            scores: [0, 1, 2, 3, 4].map(quantile),
+            top_outs: played.iter().filter(|game| game.over).count(),
+        }
+    }
+
+    /// share of games that topped out
+    pub fn top_out_rate(&self) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            self.top_outs as f64 / self.games as f64
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let [least, q1, median, q3, most] = self.scores;
+        writeln!(f, "games:        {}", self.games)?;
+        writeln!(f, "mean lines:   {:.1}", self.mean_lines)?;
+        writeln!(f, "mean pieces:  {:.1}", self.mean_pieces)?;
+        writeln!(f, "mean score:   {:.1}", self.mean_score)?;
+        writeln!(
+            f,
+            "score:        least {}, q1 {}, median {}, q3 {}, most {}",
+            least, q1, median, q3, most
+        )?;
+        write!(
+            f,
+            "top-out rate: {:.1}% ({} of {})",
+            self.top_out_rate() * 100.0,
+            self.top_outs,
+            self.games
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::bot::Bot;
+
+    #[test]
+    fn gravity_goes_by_speed() {
+        let rules = Rules {
+            size: (10, 40),
+            ..Rules::new(7)
+        };
+        let played = |speed| play(Rules { speed, ..rules }, &mut Bot::default(), 30).unwrap();
+
+        // fast enough to drop a row each input, the bot no longer gets its blocks where it wants
+        let (slow, fast) = (played(1), played(200));
+        assert_eq!(slow.record.pieces, 30);
+        assert_ne!(slow.record.score, fast.record.score);
+        assert!(slow.record.secs > 0);
+    }
+}
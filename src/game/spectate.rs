@@ -0,0 +1,307 @@
+use super::engine::Engine;
+
+use serde::{Deserialize, Serialize};
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// version of the messages below, viewers must speak the same one
+pub const VERSION: u32 = 1;
+
+/// a board is sent at most this often, and only when it changed
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// the first snapshot comes this soon after connecting
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// a viewer that can't take a message this fast is dropped
+const WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// messages waiting for a viewer at most, one further behind is dropped
+const BACKLOG: usize = 16;
+
+/// one line of json at a time from the game to its viewers, `hello` first,
+/// then the latest `snapshot` and `pause` if the game is paused
+///
+/// ~~~text
+/// {"type":"hello","version":1,"name":"violet"}
+/// {"type":"snapshot","save":"tetris-save 1\nseed 42\n..."}
+/// {"type":"pause"}
+/// ~~~
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    Hello {
+        version: u32,
+        name: String,
+    },
+    /// the whole game as `Engine::save` writes it
+    Snapshot {
+        save: String,
+    },
+    Pause,
+    Resume,
+    /// the blocks stacked out of the top
+    Over,
+    /// the game is closed
+    Bye,
+}
+
+/// `unix:<path>` for a unix socket, else `<host>:<port>` for tcp
+enum Address {
+    Tcp(String),
+    Unix(PathBuf),
+}
+
+impl Address {
+    fn parse(addr: &str) -> Self {
+        match addr.strip_prefix("unix:") {
+            Some(path) => Address::Unix(PathBuf::from(path)),
+            None => Address::Tcp(String::from(addr)),
+        }
+    }
+}
+
+/// a viewer is written to on a thread of its own, so a slow one never holds up the game
+struct Viewer {
+    line_tx: SyncSender<String>,
+    writer: JoinHandle<()>,
+}
+
+impl Viewer {
+    fn spawn(mut sink: Box<dyn Write + Send>) -> Self {
+        let (line_tx, line_rx) = mpsc::sync_channel::<String>(BACKLOG);
+        let writer = thread::spawn(move || {
+            for line in line_rx {
+                if sink.write_all(line.as_bytes()).is_err() {
+                    break;
+                }
+            }
+        });
+        Viewer { line_tx, writer }
+    }
+
+    /// false if the viewer is gone or too far behind
+    fn send(&self, line: &str) -> bool {
+        self.line_tx.try_send(String::from(line)).is_ok()
+    }
+}
+
+type Viewers = Arc<Mutex<Vec<Viewer>>>;
+
+/// viewers of a game, each one gets every message from when it connects
+pub struct Broadcast {
+    viewers: Viewers,
+    /// lines a new viewer needs to see the game as it is
+    greeting: Arc<Mutex<Vec<String>>>,
+    snapshot: String,
+    snapshot_at: Instant,
+    paused: bool,
+    /// unix socket to remove when the game is closed
+    path: Option<PathBuf>,
+}
+
+impl Broadcast {
+    /// listen on `addr` for viewers of the game played by `name`
+    pub fn listen(addr: &str, name: &str) -> Result<Self, String> {
+        let viewers: Viewers = Default::default();
+        let hello = line(&Message::Hello {
+            version: VERSION,
+            name: String::from(name),
+        });
+        let greeting = Arc::new(Mutex::new(vec![hello]));
+
+        let accept = {
+            let (viewers, greeting) = (viewers.clone(), greeting.clone());
+            move |sink: Box<dyn Write + Send>| {
+                // under the lock, so nothing is sent between the greeting and the next message
+                let mut viewers = viewers.lock().unwrap();
+                let greeting = greeting.lock().unwrap();
+                let viewer = Viewer::spawn(sink);
+                if greeting.iter().all(|l| viewer.send(l)) {
+                    viewers.push(viewer);
+                }
+            }
+        };
+
+        let path = match Address::parse(addr) {
+            Address::Tcp(addr) => {
+                let listener = TcpListener::bind(&addr).map_err(|e| format!("{}: {}", addr, e))?;
+                thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_ok() {
+                            accept(Box::new(stream));
+                        }
+                    }
+                });
+                None
+            }
+            Address::Unix(path) => {
+                let listener =
+                    UnixListener::bind(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+                thread::spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_ok() {
+                            accept(Box::new(stream));
+                        }
+                    }
+                });
+                Some(path)
+            }
+        };
+
+        Ok(Broadcast {
+            viewers,
+            greeting,
+            snapshot: String::new(),
+            snapshot_at: Instant::now() - SNAPSHOT_INTERVAL,
+            paused: false,
+            path,
+        })
+    }
+
+    /// send what changed in the game since the last time
+    pub fn update(&mut self, engine: &Engine, paused: bool) {
+        if paused != self.paused {
+            self.paused = paused;
+            self.send(&if paused {
+                Message::Pause
+            } else {
+                Message::Resume
+            });
+        }
+
+        // the last board goes out right away
+        if self.snapshot_at.elapsed() < SNAPSHOT_INTERVAL && !engine.is_over() {
+            return;
+        }
+        let save = engine.save();
+        if save != self.snapshot {
+            self.snapshot_at = Instant::now();
+            self.snapshot = save;
+            self.send(&Message::Snapshot {
+                save: self.snapshot.clone(),
+            });
+        }
+    }
+
+    /// `message` to every viewer, the ones that are gone or too far behind are dropped
+    pub fn send(&self, message: &Message) {
+        let line = line(message);
+        let mut viewers = self.viewers.lock().unwrap();
+        viewers.retain(|viewer| viewer.send(&line));
+
+        let mut greeting = self.greeting.lock().unwrap();
+        match message {
+            Message::Snapshot { .. } => {
+                greeting.truncate(1);
+                greeting.push(line);
+                if self.paused {
+                    greeting.push(self::line(&Message::Pause));
+                }
+            }
+            Message::Pause | Message::Over => greeting.push(line),
+            Message::Resume => greeting.retain(|l| *l != self::line(&Message::Pause)),
+            _ => {}
+        }
+    }
+}
+
+impl Drop for Broadcast {
+    fn drop(&mut self) {
+        self.send(&Message::Bye);
+        // viewers get what is left for them, bye included, before the game goes
+        let viewers = std::mem::take(&mut *self.viewers.lock().unwrap());
+        for Viewer { line_tx, writer } in viewers {
+            drop(line_tx);
+            writer.join().ok();
+        }
+        if let Some(path) = &self.path {
+            fs::remove_file(path).ok();
+        }
+    }
+}
+
+fn line(message: &Message) -> String {
+    format!("{}\n", serde_json::to_string(message).unwrap())
+}
+
+/// the messages of a game being watched, they come in on a thread of their own
+pub struct Feed {
+    pub name: String,
+    message_rx: Receiver<Result<Message, String>>,
+}
+
+impl Feed {
+    /// watch the game broadcast at `addr`
+    pub fn connect(addr: &str) -> Result<Self, String> {
+        let stream: Box<dyn Read + Send> = match Address::parse(addr) {
+            Address::Tcp(addr) => {
+                Box::new(TcpStream::connect(&addr).map_err(|e| format!("{}: {}", addr, e))?)
+            }
+            Address::Unix(path) => Box::new(
+                UnixStream::connect(&path).map_err(|e| format!("{}: {}", path.display(), e))?,
+            ),
+        };
+        let mut reader = BufReader::new(stream);
+
+        let name = match receive(&mut reader)? {
+            Message::Hello { version, name } if version == VERSION => name,
+            Message::Hello { version, .. } => {
+                return Err(format!(
+                    "game speaks version {}, expect version {}",
+                    version, VERSION
+                ))
+            }
+            message => return Err(format!("expect hello, got {:?}", message)),
+        };
+
+        let (message_tx, message_rx) = mpsc::channel();
+        thread::spawn(move || loop {
+            let message = receive(&mut reader);
+            let gone = message.is_err() || message == Ok(Message::Bye);
+            if message_tx.send(message).is_err() || gone {
+                break;
+            }
+        });
+        Ok(Feed { name, message_rx })
+    }
+
+    /// next message, waiting at most `timeout`, None if none came
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Result<Message, String>> {
+        match self.message_rx.recv_timeout(timeout) {
+            Ok(message) => Some(message),
+            Err(mpsc::RecvTimeoutError::Timeout) => None,
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                Some(Err(format!("{} is gone", self.name)))
+            }
+        }
+    }
+
+    /// next message, None if none came yet
+    pub fn try_recv(&self) -> Option<Result<Message, String>> {
+        match self.message_rx.try_recv() {
+            Ok(message) => Some(message),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(format!("{} is gone", self.name))),
+        }
+    }
+}
+
+fn receive(reader: &mut impl BufRead) -> Result<Message, String> {
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) => Err(String::from("connection closed")),
+        Ok(_) => {
+            serde_json::from_str(&line).map_err(|e| format!("bad message `{}`: {}", line.trim(), e))
+        }
+        Err(e) => Err(e.to_string()),
+    }
+}
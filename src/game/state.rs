@@ -1,92 +1,108 @@
 use super::block;
-use super::timer;
+use super::bot;
+use super::config;
+use super::engine;
+use super::finesse;
+use super::menu;
+use super::replay;
+use super::royale;
+use super::scores;
+use super::spectate;
 
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
+    atomic::{AtomicBool, AtomicU32, Ordering},
     Mutex,
 };
 
-#[derive(Debug)]
-pub struct Record {
-    pub secs: u32, // seconds
-    pub line: u32,
-    pub score: u32,
-    pub speed: u32,
+/// where a board sits when several share the terminal, side by side
+#[derive(Clone, Debug)]
+pub struct Seat {
+    pub index: u16,
+    pub count: u16,
+    pub name: String,
 }
 
-impl Default for Record {
+impl Default for Seat {
     fn default() -> Self {
-        Record {
-            secs: 0,
-            line: 0,
-            score: 0,
-            speed: 1,
+        Seat {
+            index: 0,
+            count: 1,
+            name: String::from("player"),
         }
     }
 }
 
-pub struct TwoBlocks {
-    curr: Box<block::FallingBlock>,
-    next: Box<block::FallingBlock>,
-}
-
-impl TwoBlocks {
-    pub fn new(curr: Box<block::FallingBlock>, next: Box<block::FallingBlock>) -> Self {
-        TwoBlocks { curr, next }
-    }
-
-    pub fn current_block(&self) -> &block::FallingBlock {
-        &*self.curr
-    }
-
-    pub fn current_block_mut(&mut self) -> &mut block::FallingBlock {
-        &mut *self.curr
-    }
-
-    pub fn next_block(&self) -> &block::FallingBlock {
-        &*self.next
-    }
-
-    pub fn push(&mut self, mut block: Box<block::FallingBlock>) -> Box<block::FallingBlock> {
-        use std::mem::swap;
-        swap(&mut self.curr, &mut self.next);
-        swap(&mut self.next, &mut block);
-        block
-    }
-}
-
 pub struct State {
     size: (usize, usize),
+    pub seat: Seat,
 
     pub quit_signal: AtomicBool,
-    pub handle_signal: AtomicBool,
 
-    pub timer: timer::Timer,
-    pub record: Mutex<Record>,
+    pub engine: Mutex<engine::Engine>,
+    /// where the falling block is drawn, to be erased before drawing it again
+    pub falling: Mutex<Option<block::PointArray>>,
+
+    pub finesse: Mutex<finesse::Finesse>,
+    /// finesse trainer, None for a normal game
+    pub trainer: Mutex<Option<finesse::Trainer>>,
+    /// bot playing instead of keys, None for a human
+    pub autoplay: Mutex<Option<bot::Autoplay>>,
+    /// show where the bot would place current block
+    pub hint: AtomicBool,
+    /// where the bot would place current block, None until it is asked
+    pub hinted: Mutex<Option<Option<block::PointArray>>>,
+
+    /// garbage lines sent by clears, until they are passed on to the opponent
+    pub sent: AtomicU32,
+    /// holds and locks until they are sent to a network peer, None unless one follows this board
+    pub moves: Mutex<Option<Vec<engine::Change>>>,
+    /// viewers the game is streamed to, None unless it is broadcast
+    pub broadcast: Mutex<Option<spectate::Broadcast>>,
+    /// the game is played elsewhere, this one only shows it
+    pub spectator: bool,
+    /// played over telnet, the files of the server are not for the player
+    pub remote: bool,
+
+    pub message: Mutex<Option<String>>,
 
-    pub two_blocks: Mutex<TwoBlocks>,
+    pub recorder: Mutex<Option<replay::Recorder>>,
+    pub replay: Mutex<Option<replay::Status>>,
+    /// the battle around the board, None unless it is a battle royale
+    pub royale: Mutex<Option<royale::Status>>,
 
-    pub stacked_blocks: Mutex<block::StackedBlock>,
+    pub config: Mutex<config::Config>,
+    pub screen: Mutex<menu::Screen>,
 
-    pub message: Mutex<Option<String>>,
+    /// high score table the game goes to, None to keep it out
+    pub mode: Mutex<Option<&'static str>>,
 }
 
 impl State {
-    pub fn new(
-        column: usize,
-        row: usize,
-        curr: block::FallingBlock,
-        next: block::FallingBlock,
-    ) -> Self {
+    pub fn new(mut engine: engine::Engine, config: config::Config) -> Self {
+        engine.set_garbage(config.garbage.clone());
         State {
-            size: (column, row),
+            size: engine.rules().size,
+            seat: Default::default(),
             quit_signal: AtomicBool::new(false),
-            handle_signal: AtomicBool::new(true),
-            timer: Default::default(),
-            record: Default::default(),
-            two_blocks: Mutex::new(TwoBlocks::new(Box::new(curr), Box::new(next))),
-            stacked_blocks: Mutex::new(block::StackedBlock::new(column, row)),
+            finesse: Mutex::new(finesse::Finesse::new(&engine)),
+            trainer: Default::default(),
+            autoplay: Default::default(),
+            hint: AtomicBool::new(false),
+            hinted: Default::default(),
+            engine: Mutex::new(engine),
+            falling: Default::default(),
+            sent: AtomicU32::new(0),
+            moves: Default::default(),
+            broadcast: Default::default(),
+            spectator: false,
+            remote: false,
             message: Default::default(),
+            recorder: Default::default(),
+            replay: Default::default(),
+            royale: Default::default(),
+            config: Mutex::new(config),
+            screen: Default::default(),
+            mode: Mutex::new(Some(scores::MARATHON)),
         }
     }
 
@@ -94,6 +110,10 @@ impl State {
         self.size
     }
 
+    pub fn rules(&self) -> engine::Rules {
+        *self.engine.lock().unwrap().rules()
+    }
+
     pub fn get_game_size(&self) -> (u16, u16) {
         let (col, row) = self.get_size();
         (2 * col as u16, row as u16)
@@ -102,35 +122,131 @@ impl State {
     pub fn quit(&self) -> bool {
         self.quit_signal.load(Ordering::Relaxed)
     }
+
+    /// one of several boards in the terminal
+    pub fn is_versus(&self) -> bool {
+        self.seat.count > 1
+    }
 }
 
 impl Drop for State {
     fn drop(&mut self) {
         self.quit_signal.store(true, Ordering::Relaxed);
 
-        let Record {
+        let engine = self.engine.lock().unwrap();
+        let engine::Record {
             secs,
             line,
             score,
             speed,
-        } = *self.record.lock().unwrap();
+            pieces,
+            ..
+        } = *engine.record();
 
         if let Some(message) = &*self.message.lock().unwrap() {
             println!("{}", message);
         }
 
-        if score == 0 {
+        // the player tells their own results, a remote one gets them over telnet
+        if self.spectator || self.remote {
             return;
         }
 
+        if self.is_versus() {
+            let engine::Stats { pps, apm, .. } = engine.stats();
+            println!(
+                "🎮 {}: {} line(s), {} attack, {} piece(s), {:.2} pps, {:.1} apm",
+                self.seat.name,
+                line,
+                engine.record().attack,
+                pieces,
+                pps,
+                apm
+            );
+        } else if score != 0 {
+            println!(
+                concat!(
+                    "😃 you got score: {}, ",
+                    "eliminated {} line(s), ",
+                    "reached a speed of {}, ",
+                    "and played for {} second(s) at this game"
+                ),
+                score, line, speed, secs
+            );
+        }
+
+        if pieces != 0 && !self.is_versus() {
+            let engine::Stats { pps, kpp, lpm, apm } = engine.stats();
+            println!(
+                "📈 {} piece(s), {:.2} pps, {:.2} kpp, {:.1} lpm, {:.1} apm, {} finesse fault(s)",
+                pieces,
+                pps,
+                kpp,
+                lpm,
+                apm,
+                self.finesse.lock().unwrap().faults
+            );
+        }
+
+        if let Some(trainer) = &*self.trainer.lock().unwrap() {
+            println!(
+                "🎯 {} of {} block(s) placed on target with the fewest moves",
+                trainer.hits,
+                trainer.hits + trainer.misses
+            );
+        }
+
+        if let (Some(mode), true) = (*self.mode.lock().unwrap(), score != 0) {
+            let score = scores::Score {
+                score,
+                line,
+                level: speed,
+                secs,
+                ..scores::Score::new(mode, self.size, self.config.lock().unwrap().player())
+            };
+            match scores::Scores::submit(score) {
+                Ok(scores::Rank {
+                    place: Some(place),
+                    personal_best: true,
+                }) => println!(
+                    "🏆 new personal best, #{} in the {} {}x{} table (see --scores)",
+                    place, mode, self.size.0, self.size.1
+                ),
+                Ok(scores::Rank {
+                    place: Some(place),
+                    personal_best: false,
+                }) => println!(
+                    "📋 #{} in the {} {}x{} table (see --scores)",
+                    place, mode, self.size.0, self.size.1
+                ),
+                Ok(scores::Rank {
+                    place: None,
+                    personal_best: true,
+                }) => println!(
+                    "🏆 new personal best in the {} {}x{} table, not in its top {}",
+                    mode,
+                    self.size.0,
+                    self.size.1,
+                    scores::TOP_COUNT
+                ),
+                Ok(_) => {}
+                Err(e) => println!("fail to save score: {}", e),
+            }
+        }
+
+        if let Some(recorder) = self.recorder.lock().unwrap().take() {
+            match recorder.finish(engine.clock(), engine.record()) {
+                Ok(path) => println!("🎞️ replay saved to {}", path.display()),
+                Err(e) => println!("fail to save replay: {}", e),
+            }
+        }
+
+        if self.seat.index != 0 {
+            return;
+        }
         println!(
-            concat!(
-                "😃 you got score: {}, ",
-                "eliminated {} line(s), ",
-                "reached a speed of {}, ",
-                "and played for {} second(s) at this game"
-            ),
-            score, line, speed, secs
+            "🌱 seed of this game: {} (replay the blocks with --seed)",
+            engine.rules().seed
         );
     }
 }
@@ -0,0 +1,326 @@
+use super::block::{Point, PointArray, BLOCKS};
+use super::bot::{self, Brain};
+use super::engine::Engine;
+use super::event::Event;
+
+use serde::{Deserialize, Serialize};
+use terminal::Color;
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// rows of the board a bot is told about, the ones above the game are empty
+pub const BOARD_ROWS: usize = 40;
+
+/// how long a bot may take to start up, and to suggest a move
+pub const READY_TIMEOUT: Duration = Duration::from_secs(10);
+pub const THINK_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Piece {
+    I,
+    O,
+    T,
+    L,
+    J,
+    S,
+    Z,
+}
+
+/// in the order of BLOCKS
+const PIECES: [Piece; 7] = [
+    Piece::J,
+    Piece::L,
+    Piece::S,
+    Piece::Z,
+    Piece::I,
+    Piece::T,
+    Piece::O,
+];
+
+impl Piece {
+    pub fn from_kind(kind: usize) -> Self {
+        PIECES[kind]
+    }
+
+    /// index in BLOCKS
+    pub fn kind(&self) -> usize {
+        PIECES.iter().position(|p| p == self).unwrap()
+    }
+
+    fn letter(&self) -> char {
+        match self {
+            Piece::I => 'I',
+            Piece::O => 'O',
+            Piece::T => 'T',
+            Piece::L => 'L',
+            Piece::J => 'J',
+            Piece::S => 'S',
+            Piece::Z => 'Z',
+        }
+    }
+
+    /// cells around the center, pointing north
+    fn cells(&self) -> [(isize, isize); 4] {
+        match self {
+            Piece::I => [(-1, 0), (0, 0), (1, 0), (2, 0)],
+            Piece::O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+            Piece::T => [(-1, 0), (0, 0), (1, 0), (0, 1)],
+            Piece::L => [(-1, 0), (0, 0), (1, 0), (1, 1)],
+            Piece::J => [(-1, 0), (0, 0), (1, 0), (-1, 1)],
+            Piece::S => [(-1, 0), (0, 0), (0, 1), (1, 1)],
+            Piece::Z => [(-1, 1), (0, 1), (0, 0), (1, 0)],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    North,
+    East,
+    South,
+    West,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Spin {
+    None,
+    Mini,
+    Full,
+}
+
+/// where a piece comes to rest, by its center
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PieceLocation {
+    #[serde(rename = "type")]
+    pub kind: Piece,
+    pub orientation: Orientation,
+    pub x: isize,
+    pub y: isize, // from the floor
+}
+
+impl PieceLocation {
+    pub fn points(&self) -> PointArray {
+        let mut points = [Point::default(); 4];
+        for (point, (x, y)) in points.iter_mut().zip(self.kind.cells()) {
+            let (x, y) = match self.orientation {
+                Orientation::North => (x, y),
+                Orientation::East => (y, -x),
+                Orientation::South => (-x, -y),
+                Orientation::West => (-y, x),
+            };
+            *point = Point::new(self.x + x, self.y + y);
+        }
+        points
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Move {
+    pub location: PieceLocation,
+    pub spin: Spin,
+}
+
+/// messages from the game to a bot
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FrontendMessage {
+    Rules {},
+    Start {
+        hold: Option<Piece>,
+        queue: Vec<Piece>, // current block first
+        combo: u32,
+        back_to_back: bool,
+        board: Vec<Vec<Option<char>>>, // from the floor, `G` for garbage
+    },
+    Stop,
+    Suggest,
+    Play {
+        #[serde(rename = "move")]
+        mv: Move,
+    },
+    NewPiece {
+        piece: Piece,
+    },
+    Quit,
+}
+
+/// messages from a bot to the game
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BotMessage {
+    Error {
+        reason: String,
+    },
+    Ready,
+    Info {
+        name: String,
+        version: String,
+        author: String,
+        features: Vec<String>,
+    },
+    Suggestion {
+        moves: Vec<Move>, // best first
+    },
+}
+
+/// what a bot is told to start from
+pub fn start(engine: &Engine) -> FrontendMessage {
+    let stacked = engine.stacked();
+    let mut board: Vec<Vec<Option<char>>> = stacked
+        .colors()
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|color| match color {
+                    Color::Reset => None,
+                    color => Some(
+                        BLOCKS
+                            .iter()
+                            .position(|b| b.color() == color)
+                            .map_or('G', |kind| Piece::from_kind(kind).letter()),
+                    ),
+                })
+                .collect()
+        })
+        .collect();
+    let column = stacked.get_column();
+    board.resize(BOARD_ROWS.max(board.len()), vec![None; column]);
+
+    FrontendMessage::Start {
+        hold: engine.hold_block().map(|b| Piece::from_kind(b.kind())),
+        queue: vec![
+            Piece::from_kind(engine.current_block().kind()),
+            Piece::from_kind(engine.next_block().kind()),
+        ],
+        combo: engine.combo(),
+        back_to_back: engine.back_to_back(),
+        board,
+    }
+}
+
+/// a bot in another process, talking json lines over its stdin and stdout
+///
+/// blocks here don't turn like srs, so a bot would lose track of the board by itself:
+/// each block starts it again from the real board, and its suggestion is reached
+/// by the inputs the move generator finds for it
+pub struct External {
+    child: Child,
+    stdin: ChildStdin,
+    /// lines of its stdout, read on a thread of their own
+    /// so a bot that hangs can't hold up the game
+    line_rx: Receiver<std::io::Result<String>>,
+
+    pub name: String,
+}
+
+impl External {
+    /// run `command`, split by whitespace, and wait until the bot is ready
+    pub fn spawn(command: &str) -> Result<Self, String> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or("empty bot command")?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("{}: {}", program, e))?;
+
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let (line_tx, line_rx) = mpsc::channel();
+        thread::spawn(move || loop {
+            let mut line = String::new();
+            let line = stdout.read_line(&mut line).map(|_| line);
+            let gone = !matches!(&line, Ok(line) if !line.is_empty());
+            if line_tx.send(line).is_err() || gone {
+                break;
+            }
+        });
+
+        let mut external = External {
+            stdin: child.stdin.take().unwrap(),
+            line_rx,
+            child,
+            name: String::from(program),
+        };
+
+        let deadline = Instant::now() + READY_TIMEOUT;
+        if let BotMessage::Info {
+            name,
+            version,
+            author,
+            ..
+        } = external.receive(deadline)?
+        {
+            external.name = format!("{} {} by {}", name, version, author);
+        }
+        external.send(&FrontendMessage::Rules {})?;
+        match external.receive(deadline)? {
+            BotMessage::Ready => Ok(external),
+            BotMessage::Error { reason } => Err(format!("{}: {}", external.name, reason)),
+            message => Err(format!(
+                "{}: expect ready, got {:?}",
+                external.name, message
+            )),
+        }
+    }
+
+    fn send(&mut self, message: &FrontendMessage) -> Result<(), String> {
+        let line = serde_json::to_string(message).map_err(|e| e.to_string())?;
+        writeln!(self.stdin, "{}", line)
+            .and_then(|_| self.stdin.flush())
+            .map_err(|e| format!("{}: {}", self.name, e))
+    }
+
+    /// next message, an error if none comes by `deadline`
+    fn receive(&mut self, deadline: Instant) -> Result<BotMessage, String> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        match self.line_rx.recv_timeout(timeout) {
+            Ok(Ok(line)) if line.is_empty() => Err(format!("{} quit", self.name)),
+            Ok(Ok(line)) => serde_json::from_str(&line)
+                .map_err(|e| format!("{}: bad message `{}`: {}", self.name, line.trim(), e)),
+            Ok(Err(e)) => Err(format!("{}: {}", self.name, e)),
+            Err(RecvTimeoutError::Timeout) => Err(format!("{} says nothing", self.name)),
+            Err(RecvTimeoutError::Disconnected) => Err(format!("{} quit", self.name)),
+        }
+    }
+}
+
+impl Brain for External {
+    fn think(&mut self, engine: &Engine) -> Result<Vec<Event>, String> {
+        self.send(&start(engine))?;
+        self.send(&FrontendMessage::Suggest)?;
+        let deadline = Instant::now() + THINK_TIMEOUT;
+        let moves = loop {
+            match self.receive(deadline)? {
+                BotMessage::Suggestion { moves } => break moves,
+                BotMessage::Error { reason } => return Err(format!("{}: {}", self.name, reason)),
+                _ => {}
+            }
+        };
+        self.send(&FrontendMessage::Stop)?;
+
+        let current = engine.current_block().kind();
+        let inputs = moves.iter().find_map(|m| {
+            let hold = m.location.kind.kind() != current;
+            bot::path(engine, hold, &m.location.points())
+        });
+        // hard drop where it is if no suggestion can be reached
+        Ok(inputs.unwrap_or_else(|| vec![Event::HardDrop]))
+    }
+}
+
+impl Drop for External {
+    fn drop(&mut self) {
+        let _ = self.send(&FrontendMessage::Quit);
+        // don't hang on a bot that doesn't quit
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
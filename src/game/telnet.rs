@@ -0,0 +1,418 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use terminal::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+// rfc 854 and the options below
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+const NAWS: u8 = 31;
+
+/// games served at once, a client past them is turned away
+pub const MAX_CLIENTS: usize = 16;
+
+/// size of a client that doesn't tell its window size
+pub const DEFAULT_SIZE: (u16, u16) = (80, 24);
+
+/// a client is waited this long to tell its window size before the game starts
+pub const NAWS_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// a key cut short is waited this long for the rest, a lone esc is the esc key after it
+const KEY_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// keys and window size of a telnet client, they come in on a thread of their own
+pub struct Keyboard {
+    size: Arc<Mutex<(u16, u16)>>,
+    event_rx: Receiver<Event>,
+}
+
+impl Keyboard {
+    /// ask the client on `stream` for keys one at a time, echoed by no one, and for its window size
+    pub fn open(stream: &TcpStream) -> Result<Self, String> {
+        let error = |e: io::Error| e.to_string();
+        let mut reader = stream.try_clone().map_err(error)?;
+        let mut writer = stream.try_clone().map_err(error)?;
+        writer
+            .write_all(&[
+                IAC,
+                WILL,
+                ECHO,
+                IAC,
+                WILL,
+                SUPPRESS_GO_AHEAD,
+                IAC,
+                DO,
+                SUPPRESS_GO_AHEAD,
+                IAC,
+                DO,
+                NAWS,
+            ])
+            .map_err(error)?;
+
+        let size = Arc::new(Mutex::new(DEFAULT_SIZE));
+        let mut parser = Parser::new(size.clone());
+        let (event_tx, event_rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buf = [0; 1024];
+            loop {
+                let timeout = (!parser.data.is_empty()).then_some(KEY_TIMEOUT);
+                if reader.set_read_timeout(timeout).is_err() {
+                    break;
+                }
+                let (events, reply) = match reader.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => parser.feed(&buf[..n]),
+                    // nothing more came, the key cut short is all there is
+                    Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                        (parser.flush(), Vec::new())
+                    }
+                    Err(_) => break,
+                };
+                if !reply.is_empty() && writer.write_all(&reply).is_err() {
+                    break;
+                }
+                if events
+                    .into_iter()
+                    .any(|event| event_tx.send(event).is_err())
+                {
+                    break;
+                }
+            }
+        });
+
+        // keys before the window size are not played
+        let deadline = Instant::now() + NAWS_TIMEOUT;
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match event_rx.recv_timeout(timeout) {
+                Ok(Event::Resize) | Err(RecvTimeoutError::Timeout) => break,
+                Ok(_) => {}
+                Err(RecvTimeoutError::Disconnected) => return Err(String::from("client left")),
+            }
+        }
+        Ok(Keyboard { size, event_rx })
+    }
+
+    /// (col, row) of the client window
+    pub fn size(&self) -> (u16, u16) {
+        *self.size.lock().unwrap()
+    }
+
+    /// next key or resize, None if none came yet, an error once the client is gone
+    pub fn try_recv(&self) -> io::Result<Option<Event>> {
+        match self.event_rx.try_recv() {
+            Ok(event) => Ok(Some(event)),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                "client left",
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Telnet {
+    Data,
+    /// after IAC
+    Command,
+    /// after IAC and one of WILL, WONT, DO, DONT
+    Option(u8),
+    /// inside IAC SB ... IAC SE
+    Sub,
+    /// after IAC inside a subnegotiation
+    SubCommand,
+}
+
+/// splits what a client sends into its keys and telnet commands
+struct Parser {
+    telnet: Telnet,
+    sub: Vec<u8>,
+    /// keys not read yet, the last one may be cut short
+    data: Vec<u8>,
+    size: Arc<Mutex<(u16, u16)>>,
+}
+
+impl Parser {
+    fn new(size: Arc<Mutex<(u16, u16)>>) -> Self {
+        Parser {
+            telnet: Telnet::Data,
+            sub: Vec::new(),
+            data: Vec::new(),
+            size,
+        }
+    }
+
+    /// events in `bytes` and what to answer the client
+    fn feed(&mut self, bytes: &[u8]) -> (Vec<Event>, Vec<u8>) {
+        let (mut events, mut reply) = (Vec::new(), Vec::new());
+        let data = &mut self.data;
+        for &byte in bytes {
+            self.telnet = match (self.telnet, byte) {
+                (Telnet::Data, IAC) => Telnet::Command,
+                (Telnet::Data, _) => {
+                    data.push(byte);
+                    Telnet::Data
+                }
+                (Telnet::Command, IAC) => {
+                    data.push(IAC);
+                    Telnet::Data
+                }
+                (Telnet::Command, SB) => {
+                    self.sub.clear();
+                    Telnet::Sub
+                }
+                (Telnet::Command, WILL | WONT | DO | DONT) => Telnet::Option(byte),
+                // go ahead, no operation and the like mean nothing here
+                (Telnet::Command, _) => Telnet::Data,
+                (Telnet::Option(verb), option) => {
+                    match (verb, option) {
+                        (WILL, SUPPRESS_GO_AHEAD | NAWS) | (DO, ECHO | SUPPRESS_GO_AHEAD) => {}
+                        // the window size won't come, the default one is it
+                        (WONT, NAWS) => events.push(Event::Resize),
+                        (WILL, _) => reply.extend([IAC, DONT, option]),
+                        (DO, _) => reply.extend([IAC, WONT, option]),
+                        _ => {}
+                    }
+                    Telnet::Data
+                }
+                (Telnet::Sub, IAC) => Telnet::SubCommand,
+                (Telnet::Sub, _) => {
+                    self.sub.push(byte);
+                    Telnet::Sub
+                }
+                (Telnet::SubCommand, IAC) => {
+                    self.sub.push(IAC);
+                    Telnet::Sub
+                }
+                (Telnet::SubCommand, SE) => {
+                    if let [NAWS, w1, w0, h1, h0] = self.sub[..] {
+                        let size = (u16::from_be_bytes([w1, w0]), u16::from_be_bytes([h1, h0]));
+                        *self.size.lock().unwrap() = size;
+                        events.push(Event::Resize);
+                    }
+                    Telnet::Data
+                }
+                (Telnet::SubCommand, _) => Telnet::Data,
+            };
+        }
+
+        let (keys, used) = keys(&self.data, false);
+        self.data.drain(..used);
+        events.extend(keys.into_iter().map(Event::Key));
+        (events, reply)
+    }
+
+    /// keys cut short, taken as they are since nothing more came
+    fn flush(&mut self) -> Vec<Event> {
+        let (keys, _) = keys(&self.data, true);
+        self.data.clear();
+        keys.into_iter().map(Event::Key).collect()
+    }
+}
+
+/// keys as a terminal sends them and the bytes they took, escape sequences for the
+/// ones without a character; what is left may be a key cut short, unless `flush`
+fn keys(data: &[u8], flush: bool) -> (Vec<KeyEvent>, usize) {
+    let (mut keys, mut used) = (Vec::new(), 0);
+    while used < data.len() {
+        match key(&data[used..], flush) {
+            Some((key, n)) => {
+                keys.extend(key);
+                used += n;
+            }
+            None => break,
+        }
+    }
+    (keys, used)
+}
+
+/// the first key of `data` and its bytes, no key for bytes that mean nothing,
+/// None if it is cut short and more may come
+fn key(data: &[u8], flush: bool) -> Option<(Option<KeyEvent>, usize)> {
+    let plain = |code| Some(KeyEvent::new(code, KeyModifiers::empty()));
+    let key = match data[0] {
+        0x1b => return escape(data, flush),
+        b'\r' => {
+            // enter comes as cr nul or cr lf
+            return match data.get(1) {
+                Some(b'\0' | b'\n') => Some((plain(KeyCode::Enter), 2)),
+                None if !flush => None,
+                _ => Some((plain(KeyCode::Enter), 1)),
+            };
+        }
+        b'\n' => plain(KeyCode::Enter),
+        b'\t' => plain(KeyCode::Tab),
+        0x7f | 0x08 => plain(KeyCode::Backspace),
+        byte @ 0x01..=0x1a => Some(KeyEvent::new(
+            KeyCode::Char((byte - 1 + b'a') as char),
+            KeyModifiers::CONTROL,
+        )),
+        byte if byte < 0x20 => None,
+        byte => {
+            let len = match byte.leading_ones() {
+                0 => 1,
+                n @ 2..=4 => n as usize,
+                _ => return Some((None, 1)),
+            };
+            if data.len() < len {
+                return if flush {
+                    Some((None, data.len()))
+                } else {
+                    None
+                };
+            }
+            return match std::str::from_utf8(&data[..len])
+                .ok()
+                .and_then(|c| c.chars().next())
+            {
+                Some(c) if c.is_control() => Some((None, len)),
+                Some(c) if c.is_ascii_uppercase() => Some((
+                    Some(KeyEvent::new(KeyCode::Char(c), KeyModifiers::SHIFT)),
+                    len,
+                )),
+                Some(c) => Some((plain(KeyCode::Char(c)), len)),
+                None => Some((None, 1)),
+            };
+        }
+    };
+    Some((key, 1))
+}
+
+/// longest escape sequence read, longer ones are thrown away
+const MAX_ESCAPE: usize = 16;
+
+/// the key of an escape sequence, `esc [ 1 ; 5 A` is ctrl+up,
+/// a lone esc is the esc key only once nothing follows it
+fn escape(data: &[u8], flush: bool) -> Option<(Option<KeyEvent>, usize)> {
+    let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::empty());
+    let alt = |c: u8| KeyEvent::new(KeyCode::Char(c as char), KeyModifiers::ALT);
+    match data.get(1) {
+        None if flush => return Some((Some(esc), 1)),
+        None => return None,
+        Some(b'[' | b'O') => {}
+        // alt sends esc before the character
+        Some(&c @ 0x20..=0x7e) => return Some((Some(alt(c)), 2)),
+        Some(_) => return Some((Some(esc), 1)),
+    }
+
+    let end = data[2..]
+        .iter()
+        .position(|b| !b.is_ascii_digit() && *b != b';')
+        .map(|i| i + 2);
+    let end = match end {
+        Some(end) if end < MAX_ESCAPE => end,
+        Some(end) => return Some((None, end + 1)),
+        None if data.len() >= MAX_ESCAPE => return Some((None, data.len())),
+        // alt+[ or alt+o with nothing after
+        None if flush && data.len() == 2 => return Some((Some(alt(data[1])), 2)),
+        None if flush => return Some((None, data.len())),
+        None => return None,
+    };
+
+    let params = std::str::from_utf8(&data[2..end]).unwrap_or_default();
+    let mut numbers = params.split(';').map(|n| n.parse::<u8>().unwrap_or(1));
+    let number = numbers.next().unwrap_or(1);
+    // 1 + shift 1, alt 2, ctrl 4
+    let bits = numbers.next().unwrap_or(1).saturating_sub(1);
+    let modifiers = [
+        (1, KeyModifiers::SHIFT),
+        (2, KeyModifiers::ALT),
+        (4, KeyModifiers::CONTROL),
+    ]
+    .into_iter()
+    .filter(|(bit, _)| bits & bit != 0)
+    .fold(KeyModifiers::empty(), |all, (_, modifier)| all | modifier);
+
+    let code = match data[end] {
+        b'A' => KeyCode::Up,
+        b'B' => KeyCode::Down,
+        b'C' => KeyCode::Right,
+        b'D' => KeyCode::Left,
+        b'H' => KeyCode::Home,
+        b'F' => KeyCode::End,
+        b'Z' => KeyCode::BackTab,
+        c @ b'P'..=b'S' => KeyCode::F(c - b'P' + 1),
+        b'~' => match number {
+            1 | 7 => KeyCode::Home,
+            2 => KeyCode::Insert,
+            3 => KeyCode::Delete,
+            4 | 8 => KeyCode::End,
+            5 => KeyCode::PageUp,
+            6 => KeyCode::PageDown,
+            11..=15 => KeyCode::F(number - 10),
+            17..=21 => KeyCode::F(number - 11),
+            23 | 24 => KeyCode::F(number - 12),
+            _ => KeyCode::Null,
+        },
+        _ => KeyCode::Null,
+    };
+    Some((Some(KeyEvent::new(code, modifiers)), end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser() -> Parser {
+        Parser::new(Arc::new(Mutex::new(DEFAULT_SIZE)))
+    }
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::empty()))
+    }
+
+    #[test]
+    fn keys_split_across_reads() {
+        let mut parser = parser();
+        assert_eq!(parser.feed(b"\x1b").0, []);
+        assert_eq!(parser.feed(b"[").0, []);
+        assert_eq!(parser.feed(b"A").0, [key(KeyCode::Up)]);
+
+        assert_eq!(parser.feed(b"\r").0, []);
+        assert_eq!(
+            parser.feed(b"\0x").0,
+            [key(KeyCode::Enter), key(KeyCode::Char('x'))]
+        );
+
+        assert_eq!(parser.feed(&"é".as_bytes()[..1]).0, []);
+        assert_eq!(
+            parser.feed(&"é".as_bytes()[1..]).0,
+            [key(KeyCode::Char('é'))]
+        );
+        assert!(parser.flush().is_empty());
+    }
+
+    #[test]
+    fn lone_esc_once_nothing_follows() {
+        let mut parser = parser();
+        assert_eq!(parser.feed(b"\x1b").0, []);
+        assert_eq!(parser.flush(), [key(KeyCode::Esc)]);
+
+        assert_eq!(
+            parser.feed(b"\x1bx").0,
+            [Event::Key(KeyEvent::new(
+                KeyCode::Char('x'),
+                KeyModifiers::ALT
+            ))]
+        );
+        assert_eq!(
+            parser.feed(b"\x1b[1;5C").0,
+            [Event::Key(KeyEvent::new(
+                KeyCode::Right,
+                KeyModifiers::CONTROL
+            ))]
+        );
+    }
+}
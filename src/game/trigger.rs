@@ -1,21 +1,15 @@
 use super::painter;
-use super::state;
 
 use std::sync::{
-    mpsc::{sync_channel, Receiver, SyncSender},
+    mpsc::{sync_channel, Receiver},
     Arc,
 };
 
+use std::io;
 use std::thread::{self, JoinHandle};
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
-use terminal::{Event, KeyCode, KeyEvent, KeyModifiers};
-
-const INITIAL_DROP_INTERVAL_SECOND_COUNT: u64 = 2;
-
-fn speed_to_duration(speed: u32) -> Duration {
-    Duration::from_secs(INITIAL_DROP_INTERVAL_SECOND_COUNT) / speed
-}
+use terminal::{error::ErrorKind, Event};
 
 #[derive(Default)]
 pub struct Trigger {
@@ -31,109 +25,30 @@ impl Drop for Trigger {
 }
 
 impl Trigger {
-    pub fn start(
-        &mut self,
-        painter: Arc<painter::Painter>,
-        state: Arc<state::State>,
-    ) -> (Receiver<u64>, Receiver<Event>) {
-        let (_, timer_rx) = self.timer_thread(state.clone());
-        let (event_tx, event_rx) = self.event_thread(painter, state.clone());
-        self.auto_drop_thread(event_tx, state.clone());
-
-        (timer_rx, event_rx)
+    /// only keyboard events, time is kept by engine
+    pub fn start(&mut self, painter: Arc<painter::Painter>) -> Receiver<Event> {
+        self.event_thread(painter)
     }
 
-    fn timer_thread(&mut self, state: Arc<state::State>) -> (SyncSender<u64>, Receiver<u64>) {
-        let mut duration = Duration::from_secs(1);
-
-        let (tx, rx) = sync_channel(0);
-        let tx_clone = tx.clone();
+    fn event_thread(&mut self, painter: Arc<painter::Painter>) -> Receiver<Event> {
+        let (tx, rx) = sync_channel(1);
 
         let handle = thread::spawn(move || loop {
-            let (lock, cond) = state.timer.lock_cond();
-            let mut keeper = lock.lock().unwrap();
-
-            while keeper.is_paused() {
-                keeper = cond.wait(keeper).unwrap();
-            }
-
-            if let Some(schedule) = keeper.scheduled_time::<0>(duration) {
-                drop(keeper);
-
-                let diff = schedule.duration_since(Instant::now());
-                if diff.is_zero() {
-                    if tx.send(duration.as_secs()).is_err() {
+            match painter.get_event() {
+                Ok(Some(event)) => {
+                    if tx.send(event).is_err() {
                         break;
                     }
-                    duration += Duration::from_secs(1);
-                } else {
-                    thread::sleep(diff);
-                }
-            } else {
-                panic!("keeper ensure no pause");
-            }
-        });
-
-        self.threads.push(handle);
-        (tx_clone, rx)
-    }
-
-    fn event_thread(
-        &mut self,
-        painter: Arc<painter::Painter>,
-        _: Arc<state::State>,
-    ) -> (SyncSender<Event>, Receiver<Event>) {
-        let (tx, rx) = sync_channel(1);
-        let tx_clone = tx.clone();
-
-        let handle = thread::spawn(move || loop {
-            if let Some(event) = painter.get_event().unwrap_or(None) {
-                if tx.send(event).is_err() {
-                    break;
                 }
+                Ok(None) => {}
+                // a remote keyboard is gone, the game ends with it
+                Err(ErrorKind::IoError(e)) if e.kind() == io::ErrorKind::ConnectionAborted => break,
+                Err(_) => {}
             }
             thread::sleep(Duration::from_millis(20));
         });
 
         self.threads.push(handle);
-        (tx_clone, rx)
-    }
-
-    fn auto_drop_thread(&mut self, keyboard_sender: SyncSender<Event>, state: Arc<state::State>) {
-        let handler = thread::spawn(move || loop {
-            let speed = state.record.lock().unwrap().speed;
-            let duration = speed_to_duration(speed);
-
-            let (lock, cond) = state.timer.lock_cond();
-            let mut keeper = lock.lock().unwrap();
-
-            while keeper.is_paused() {
-                keeper = cond.wait(keeper).unwrap();
-            }
-
-            if let Some(schedule) = keeper.scheduled_time::<1>(duration) {
-                drop(keeper);
-
-                let diff = schedule.duration_since(Instant::now());
-                if diff.is_zero() {
-                    if keyboard_sender
-                        .send(Event::Key(KeyEvent {
-                            code: KeyCode::Down,
-                            modifiers: KeyModifiers::empty(),
-                        }))
-                        .is_err()
-                    {
-                        break;
-                    }
-                    thread::sleep(duration / 2);
-                } else {
-                    thread::sleep(diff);
-                }
-            } else {
-                panic!("keeper ensure no pause");
-            }
-        });
-
-        self.threads.push(handler);
+        rx
     }
 }
@@ -0,0 +1,181 @@
+use super::event;
+use super::frame::{self, Frame};
+use super::handler::{self, Handler};
+use super::{menu, painter, state};
+
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use terminal::Event;
+
+/// time between two inputs of a bot rival, quicker than autoplay as its pieces
+/// per second are kept by `Autoplay::pps`
+const RIVAL_INTERVAL: Duration = Duration::from_millis(30);
+
+/// boards played side by side from one keyboard, each on its own keys,
+/// garbage sent by the clears of one rises on the others
+pub struct Versus {
+    states: Vec<Arc<state::State>>,
+}
+
+impl Versus {
+    pub fn new(states: Vec<Arc<state::State>>) -> Self {
+        Versus { states }
+    }
+
+    pub fn run(self, event_rx: Receiver<Event>, painter: &Arc<painter::Painter>) {
+        for state in &self.states {
+            state.engine.lock().unwrap().take_changes();
+        }
+        self.resize(painter);
+        painter.flush().unwrap();
+
+        let mut last = Instant::now();
+        let mut autoplay = last;
+        while !self.quit() {
+            let raw = match event_rx.recv_timeout(handler::TICK) {
+                Ok(raw) => Some(raw),
+                Err(RecvTimeoutError::Timeout) => None,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+
+            // every board goes by the same clock
+            let now = Instant::now();
+            if self.is_playing() {
+                let millis = Duration::from_millis(now.duration_since(last).as_millis() as u64);
+                last += millis;
+                for state in &self.states {
+                    state.engine.lock().unwrap().tick(millis);
+                }
+            } else {
+                last = now;
+            }
+
+            if let Some(raw) = raw {
+                self.input(raw, painter);
+            }
+            if now >= autoplay {
+                autoplay = now + RIVAL_INTERVAL;
+                for state in &self.states {
+                    Handler::autoplay(painter, state);
+                }
+            }
+
+            let mut losers = Vec::new();
+            for (i, state) in self.states.iter().enumerate() {
+                if Handler::update(painter, state) {
+                    losers.push(i);
+                }
+            }
+            if !losers.is_empty() {
+                self.finish(&losers);
+            }
+            self.exchange();
+            painter.flush().unwrap();
+        }
+    }
+
+    fn quit(&self) -> bool {
+        self.states.iter().any(|state| state.quit())
+    }
+
+    fn is_playing(&self) -> bool {
+        matches!(*self.states[0].screen.lock().unwrap(), menu::Screen::Game)
+    }
+
+    /// garbage sent by one board rises on all the others
+    fn exchange(&self) {
+        for (i, state) in self.states.iter().enumerate() {
+            let sent = state.sent.swap(0, Ordering::Relaxed);
+            for (_, other) in self.states.iter().enumerate().filter(|(j, _)| *j != i) {
+                other.engine.lock().unwrap().receive(sent);
+            }
+        }
+    }
+
+    /// boards `losers` topped out in the same frame, the game ends,
+    /// a draw if no board is left
+    fn finish(&self, losers: &[usize]) {
+        let names = |lost: bool| -> String {
+            let names: Vec<&str> = self
+                .states
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| losers.contains(i) == lost)
+                .map(|(_, state)| state.seat.name.as_str())
+                .collect();
+            names.join(", ")
+        };
+        let message = if losers.len() == self.states.len() {
+            format!("🏁 draw, {} topped out together", names(true))
+        } else {
+            format!("🏁 {} wins, {} topped out", names(false), names(true))
+        };
+
+        let mut shown = self.states[0].message.lock().unwrap();
+        // the first reason is the one to show
+        if shown.is_none() {
+            *shown = Some(message);
+        }
+        drop(shown);
+
+        for state in &self.states {
+            state.quit_signal.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn resize(&self, painter: &Arc<painter::Painter>) {
+        frame::GameFrame::flush_terminal_size(painter);
+        if frame::GameFrame::test_terminal_size(&self.states[0]).is_err() {
+            *self.states[0].message.lock().unwrap() =
+                Some(String::from("terminal size is too small"));
+            self.states[0].quit_signal.store(true, Ordering::Relaxed);
+            return;
+        }
+
+        painter.clear_all().unwrap();
+        for state in &self.states {
+            Handler::draw_frames(painter, state);
+        }
+    }
+
+    /// pause and quit go for every board, other keys to the board they are bound for
+    fn input(&self, raw: Event, painter: &Arc<painter::Painter>) {
+        if let Event::Resize = raw {
+            return self.resize(painter);
+        }
+
+        let events: Vec<event::Event> = self
+            .states
+            .iter()
+            .map(|state| state.config.lock().unwrap().keymap.get(&raw))
+            .collect();
+
+        if events.contains(&event::Event::Quit) {
+            for state in &self.states {
+                state.quit_signal.store(true, Ordering::Relaxed);
+            }
+        } else if events.contains(&event::Event::Toggle) {
+            self.toggle(painter);
+        } else if self.is_playing() {
+            for (state, event) in self.states.iter().zip(events) {
+                Handler::apply(event, painter, state);
+            }
+        }
+    }
+
+    fn toggle(&self, painter: &Arc<painter::Painter>) {
+        let pause = self.is_playing();
+        for state in &self.states {
+            if pause {
+                *state.screen.lock().unwrap() = menu::Screen::Pause;
+                frame::GameFrame::draw_pause(painter, state);
+            } else {
+                *state.screen.lock().unwrap() = menu::Screen::Game;
+                frame::GameFrame::draw_inner(painter, state);
+            }
+        }
+    }
+}
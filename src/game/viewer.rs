@@ -0,0 +1,101 @@
+use super::engine::Engine;
+use super::event::Event;
+use super::frame::{self, Frame};
+use super::handler::{self, Handler};
+use super::spectate::{Feed, Message};
+use super::{menu, painter, state};
+
+use std::sync::atomic::Ordering;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
+
+/// shows a game played elsewhere as its snapshots come in, keys only quit
+pub struct Viewer {
+    state: Arc<state::State>,
+    feed: Feed,
+}
+
+impl Viewer {
+    pub fn new(state: Arc<state::State>, feed: Feed) -> Self {
+        Viewer { state, feed }
+    }
+
+    pub fn run(self, event_rx: Receiver<terminal::Event>, painter: &Arc<painter::Painter>) {
+        Handler::resize(painter, &self.state);
+        painter.flush().unwrap();
+
+        while !self.state.quit() {
+            match event_rx.recv_timeout(handler::TICK) {
+                Ok(raw) => self.input(raw, painter),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            while let Some(message) = self.feed.try_recv() {
+                self.receive(message, painter);
+                if self.state.quit() {
+                    break;
+                }
+            }
+            painter.flush().unwrap();
+        }
+    }
+
+    fn receive(&self, message: Result<Message, String>, painter: &Arc<painter::Painter>) {
+        let message = match message {
+            Ok(message) => message,
+            Err(e) => return self.stop(format!("🔌 {}", e)),
+        };
+
+        match message {
+            Message::Snapshot { save } => match Engine::restore(&save) {
+                Ok(engine) => {
+                    *self.state.engine.lock().unwrap() = engine;
+                    if matches!(*self.state.screen.lock().unwrap(), menu::Screen::Game) {
+                        frame::GameFrame::draw_inner(painter, &self.state);
+                    }
+                    frame::RecordFrame::draw_inner(painter, &self.state);
+                    frame::NextBlockFrame::reset_inner(painter, &self.state);
+                    frame::NextBlockFrame::draw_inner(painter, &self.state);
+                    frame::HoldFrame::reset_inner(painter, &self.state);
+                    frame::HoldFrame::draw_inner(painter, &self.state);
+                }
+                Err(e) => self.stop(format!("🔌 bad snapshot: {}", e)),
+            },
+            Message::Pause => {
+                *self.state.screen.lock().unwrap() = menu::Screen::Pause;
+                frame::GameFrame::draw_pause(painter, &self.state);
+            }
+            Message::Resume => {
+                *self.state.screen.lock().unwrap() = menu::Screen::Game;
+                frame::GameFrame::draw_inner(painter, &self.state);
+            }
+            Message::Over => {
+                *self.state.message.lock().unwrap() = Some(format!(
+                    "🏁 the blocks of {} stacked out of the top",
+                    self.feed.name
+                ));
+            }
+            Message::Bye => self.stop(format!("🔌 {} closed the game", self.feed.name)),
+            Message::Hello { .. } => self.stop(format!("🔌 {} said hello again", self.feed.name)),
+        }
+    }
+
+    fn stop(&self, message: String) {
+        let mut shown = self.state.message.lock().unwrap();
+        // the end of the game is the one to show
+        if shown.is_none() {
+            *shown = Some(message);
+        }
+        self.state.quit_signal.store(true, Ordering::Relaxed);
+    }
+
+    fn input(&self, raw: terminal::Event, painter: &Arc<painter::Painter>) {
+        let event = self.state.config.lock().unwrap().keymap.get(&raw);
+        match event {
+            Event::Quit => self.state.quit_signal.store(true, Ordering::Relaxed),
+            Event::Resize => Handler::resize(painter, &self.state),
+            _ => {}
+        }
+    }
+}
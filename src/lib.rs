@@ -0,0 +1,55 @@
+//! tetris game, the engine runs without terminal
+//!
+//! ~~~
+//! use std::time::Duration;
+//! use tetris::{Engine, Event, Rules};
+//!
+//! let mut engine = Engine::new(Rules::new(42));
+//! engine.apply(Event::HardDrop);
+//! engine.tick(Duration::from_secs(3));
+//! assert_eq!(engine.pieces(), 1);
+//! ~~~
+
+mod game;
+
+pub use game::block::{
+    Block, FallingBlock, Placement, Point, PointArray, StackedBlock, BLOCKS, MAX_COLUMN,
+    ORIENTATION_COUNT, POINT_OF_BLOCK_COUNT,
+};
+pub use game::engine::{Change, Engine, Record, Rules, Stats};
+pub use game::event::Event;
+pub use game::scores::Scores;
+pub use game::{Config, Game, Replay};
+
+/// the built-in bot, its weights and strengths
+pub mod bot {
+    pub use crate::game::bot::{Bot, Brain, Features, Strength, Weights, LEVELS};
+}
+
+/// weights trained by playing candidates against each other
+pub mod genetic {
+    pub use crate::game::genetic::{Candidate, Evolution};
+}
+
+/// every landing of a block and the inputs taking it there
+pub mod movegen {
+    pub use crate::game::movegen::{generate, Lock};
+}
+
+/// battle royale settings
+pub mod royale {
+    pub use crate::game::royale::{Targeting, MAX_BOTS, TARGETINGS};
+}
+
+/// games played without terminal, as fast as the brain thinks
+pub mod sim {
+    pub use crate::game::sim::{play, run, Played, Summary};
+}
+
+/// the tetris bot protocol, and a bot program speaking it
+pub mod tbp {
+    pub use crate::game::tbp::{
+        BotMessage, External, FrontendMessage, Move, Orientation, Piece, PieceLocation, Spin,
+        BOARD_ROWS,
+    };
+}
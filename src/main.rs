@@ -1,8 +1,84 @@
-mod game;
+mod options;
+
+use tetris::bot::{Bot, Weights};
+use tetris::tbp;
+use tetris::{Config, Game, Replay, Scores};
 
 pub fn main() -> Result<(), String> {
-    let mut game = game::Game::new();
-    if let Err(s) = game.start() {
+    let print = |s: String| {
+        println!("{}", s);
+        s
+    };
+
+    let options = options::Options::parse(std::env::args().skip(1)).map_err(print)?;
+    if options.help {
+        println!("{}", options::USAGE);
+        return Ok(());
+    }
+
+    if options.scores {
+        println!("{}", Scores::load().map_err(print)?);
+        return Ok(());
+    }
+
+    let config = Config::load().map_err(print)?;
+    let weights = match &options.weights {
+        Some(path) => Weights::load(path).map_err(print)?,
+        None => Weights::default(),
+    };
+
+    if let Some(addr) = &options.serve {
+        return Game::serve(config, addr, options.seed).map_err(print);
+    }
+
+    let mut game = if let Some(addr) = &options.spectate {
+        Game::spectate(config, addr).map_err(print)?
+    } else if let Some(path) = &options.replay {
+        let replay = Replay::load(path).map_err(print)?;
+        Game::replay(config, replay)
+    } else if options.resume {
+        Game::resume(config).map_err(print)?
+    } else if options.versus {
+        let seed = options.seed.unwrap_or_else(rand::random);
+        Game::versus(config, seed)
+    } else if let Some(addr) = &options.host {
+        let seed = options.seed.unwrap_or_else(rand::random);
+        println!("waiting for a player on {}", addr);
+        Game::host(config, seed, addr).map_err(print)?
+    } else if let Some(addr) = &options.join {
+        Game::join(config, addr).map_err(print)?
+    } else if let Some(strength) = options.versus_bot {
+        let seed = options.seed.unwrap_or_else(rand::random);
+        Game::versus_bot(config, seed, weights, strength)
+    } else if let Some((bots, strength)) = options.royale {
+        let seed = options.seed.unwrap_or_else(rand::random);
+        let targeting = options.targeting.unwrap_or_default();
+        Game::royale(config, seed, bots, targeting, weights, strength)
+    } else if options.trainer {
+        let seed = options.seed.unwrap_or_else(rand::random);
+        Game::trainer(config, seed)
+    } else {
+        let seed = options.seed.unwrap_or_else(rand::random);
+        Game::new(config, seed)
+    };
+    // a game that fails to begin keeps its save and stays out of the high scores
+    let started = (|| {
+        if options.autoplay {
+            game.autoplay(Box::new(Bot::new(weights)));
+        }
+        if let Some(command) = &options.bot {
+            game.autoplay(Box::new(tbp::External::spawn(command)?));
+        }
+        if let Some(addr) = &options.broadcast {
+            game.broadcast(addr)?;
+        }
+        if let Some(path) = &options.record {
+            game.record(path)?;
+        }
+        game.start()
+    })();
+    if let Err(s) = started {
+        game.abort();
         println!("{}", s);
         return Err(s);
     }
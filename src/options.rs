@@ -0,0 +1,372 @@
+use tetris::bot::{self, Strength};
+use tetris::royale::{self, Targeting};
+
+use std::path::PathBuf;
+
+/// command line options
+#[derive(Default)]
+pub struct Options {
+    pub help: bool,
+    pub seed: Option<u64>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    pub resume: bool,
+    pub scores: bool,
+    pub trainer: bool,
+    pub autoplay: bool,
+    pub bot: Option<String>,
+    pub weights: Option<PathBuf>,
+    pub versus: bool,
+    pub versus_bot: Option<Strength>,
+    /// address to listen on, `127.0.0.1:<port>` for a bare port
+    pub host: Option<String>,
+    pub join: Option<String>,
+    pub broadcast: Option<String>,
+    pub spectate: Option<String>,
+    /// address to listen on, `127.0.0.1:<port>` for a bare port
+    pub serve: Option<String>,
+    /// bots of a battle royale and the strength they all play at
+    pub royale: Option<(usize, Strength)>,
+    pub targeting: Option<Targeting>,
+}
+
+pub const USAGE: &str = concat!(
+    "usage: tetris [options]\n",
+    "\n",
+    "options:\n",
+    "    --seed <number>    seed of the block sequence, same seed gives same blocks\n",
+    "    --record <file>    save a replay of the game to file\n",
+    "    --replay <file>    watch a replay saved by --record\n",
+    "    --resume           go on with the game saved by `s` in pause\n",
+    "    --scores           print the high score tables\n",
+    "    --autoplay         let the built-in bot play\n",
+    "    --weights <file>   weights for --autoplay, --versus-bot or --royale, as written by tetris-train\n",
+    "    --bot <command>    let a bot speaking the tetris bot protocol play\n",
+    "    --versus           two players side by side, wasd against the arrows\n",
+    "    --versus-bot <level>\n",
+    "                       play against the built-in bot: easy, normal or hard\n",
+    "    --royale <bots>    battle royale against 1 to 98 easy bots, the last one standing wins\n",
+    "    --targeting <random|attackers|kos|badges>\n",
+    "                       who your attacks go to at first, keys 1 to 4 change it\n",
+    "    --bot-pps <number> pieces per second the bot plays at most\n",
+    "    --bot-depth <1|2>  blocks the bot looks at, current only or with next\n",
+    "    --bot-mistakes <number>\n",
+    "                       chance from 0 to 1 the bot places a block anywhere\n",
+    "    --host <[addr:]port>\n",
+    "                       wait for a player to join a versus game over the network,\n",
+    "                       on this machine only unless an address is given\n",
+    "    --join <addr:port> join a versus game hosted over the network\n",
+    "    --broadcast <addr> stream the game to viewers on a tcp address or unix:<path>\n",
+    "    --spectate <addr>  watch a game streamed by --broadcast\n",
+    "    --serve <[addr:]port>\n",
+    "                       serve a game to each telnet client connecting,\n",
+    "                       on this machine only unless an address is given\n",
+    "    --trainer          finesse trainer: place each block on its target with the fewest moves\n",
+    "    -h, --help         print this message",
+);
+
+/// a bare port listens on this machine only, `<addr>:<port>` on the address given
+fn listen_address(value: &str) -> Result<String, String> {
+    match value.parse::<u16>() {
+        Ok(port) => Ok(format!("127.0.0.1:{}", port)),
+        Err(_) if value.contains(':') => Ok(value.to_string()),
+        Err(_) => Err(format!("invalid port `{}`", value)),
+    }
+}
+
+impl Options {
+    pub fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut options = Options::default();
+        let mut level = None;
+        let mut bots = None;
+        let (mut pps, mut depth, mut mistakes) = (None, None, None);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => options.help = true,
+                "--seed" => {
+                    let value = args.next().ok_or("--seed needs a number")?;
+                    options.seed = Some(
+                        value
+                            .parse()
+                            .map_err(|_| format!("invalid seed `{}`", value))?,
+                    );
+                }
+                "--record" => {
+                    let value = args.next().ok_or("--record needs a file")?;
+                    options.record = Some(PathBuf::from(value));
+                }
+                "--replay" => {
+                    let value = args.next().ok_or("--replay needs a file")?;
+                    options.replay = Some(PathBuf::from(value));
+                }
+                "--resume" => options.resume = true,
+                "--scores" => options.scores = true,
+                "--trainer" => options.trainer = true,
+                "--autoplay" => options.autoplay = true,
+                "--versus" => options.versus = true,
+                "--versus-bot" => {
+                    let value = args.next().ok_or("--versus-bot needs a level")?;
+                    level = Some(Strength::level(&value).ok_or_else(|| {
+                        format!(
+                            "unknown level `{}`, expect one of {}",
+                            value,
+                            bot::LEVELS.join(", ")
+                        )
+                    })?);
+                }
+                "--host" => {
+                    let value = args.next().ok_or("--host needs a port")?;
+                    options.host = Some(listen_address(&value)?);
+                }
+                "--join" => {
+                    let value = args.next().ok_or("--join needs an address")?;
+                    options.join = Some(value);
+                }
+                "--broadcast" => {
+                    let value = args.next().ok_or("--broadcast needs an address")?;
+                    options.broadcast = Some(value);
+                }
+                "--spectate" => {
+                    let value = args.next().ok_or("--spectate needs an address")?;
+                    options.spectate = Some(value);
+                }
+                "--serve" => {
+                    let value = args.next().ok_or("--serve needs a port")?;
+                    options.serve = Some(listen_address(&value)?);
+                }
+                "--royale" => {
+                    let value = args.next().ok_or("--royale needs a number of bots")?;
+                    bots = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|bots| (1..=royale::MAX_BOTS).contains(bots))
+                            .ok_or_else(|| {
+                                format!(
+                                    "invalid bots `{}`, expect 1 to {}",
+                                    value,
+                                    royale::MAX_BOTS
+                                )
+                            })?,
+                    );
+                }
+                "--targeting" => {
+                    let value = args.next().ok_or("--targeting needs a strategy")?;
+                    options.targeting = Some(Targeting::from_name(&value).ok_or_else(|| {
+                        let names: Vec<&str> =
+                            royale::TARGETINGS.iter().map(|t| t.name()).collect();
+                        format!(
+                            "unknown targeting `{}`, expect one of {}",
+                            value,
+                            names.join(", ")
+                        )
+                    })?);
+                }
+                "--bot-pps" => {
+                    let value = args.next().ok_or("--bot-pps needs a number")?;
+                    pps = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|pps: &f32| *pps > 0.0)
+                            .ok_or_else(|| format!("invalid pieces per second `{}`", value))?,
+                    );
+                }
+                "--bot-depth" => {
+                    let value = args.next().ok_or("--bot-depth needs 1 or 2")?;
+                    depth = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|depth| (1..=2).contains(depth))
+                            .ok_or_else(|| format!("invalid depth `{}`, expect 1 or 2", value))?,
+                    );
+                }
+                "--bot-mistakes" => {
+                    let value = args.next().ok_or("--bot-mistakes needs a number")?;
+                    mistakes = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|m| (0.0..=1.0).contains(m))
+                            .ok_or_else(|| format!("mistakes `{}` is not from 0 to 1", value))?,
+                    );
+                }
+                "--weights" => {
+                    let value = args.next().ok_or("--weights needs a file")?;
+                    options.weights = Some(PathBuf::from(value));
+                }
+                "--bot" => {
+                    let value = args.next().ok_or("--bot needs a command")?;
+                    options.bot = Some(value);
+                }
+                _ => return Err(format!("unknown option `{}`\n\n{}", arg, USAGE)),
+            }
+        }
+
+        if options.resume && (options.seed.is_some() || options.replay.is_some()) {
+            return Err(String::from("--resume can't go with --seed or --replay"));
+        }
+        if options.resume && options.record.is_some() {
+            return Err(String::from(
+                "a resumed game can't be recorded from the middle",
+            ));
+        }
+
+        if options.trainer
+            && (options.replay.is_some() || options.resume || options.record.is_some())
+        {
+            return Err(String::from(
+                "--trainer can't go with --replay, --resume or --record",
+            ));
+        }
+
+        if (options.autoplay || options.bot.is_some())
+            && (options.replay.is_some() || options.trainer)
+        {
+            return Err(String::from(
+                "--autoplay or --bot can't go with --replay or --trainer",
+            ));
+        }
+        if options.autoplay && options.bot.is_some() {
+            return Err(String::from("--autoplay can't go with --bot"));
+        }
+        if level.is_some() && bots.is_some() {
+            return Err(String::from("--versus-bot can't go with --royale"));
+        }
+        // royale bots are many, they play easy unless told otherwise
+        let strength = level.or_else(|| bots.and_then(|_| Strength::level("easy")));
+        if let Some(mut strength) = strength {
+            strength.pps = pps.unwrap_or(strength.pps);
+            strength.depth = depth.unwrap_or(strength.depth);
+            strength.mistakes = mistakes.unwrap_or(strength.mistakes);
+            match bots {
+                Some(bots) => options.royale = Some((bots, strength)),
+                None => options.versus_bot = Some(strength),
+            }
+        } else if pps.is_some() || depth.is_some() || mistakes.is_some() {
+            return Err(String::from(
+                "--bot-pps, --bot-depth and --bot-mistakes only go with --versus-bot or --royale",
+            ));
+        }
+        if options.weights.is_some()
+            && !options.autoplay
+            && options.versus_bot.is_none()
+            && options.royale.is_none()
+        {
+            return Err(String::from(
+                "--weights only goes with --autoplay, --versus-bot or --royale",
+            ));
+        }
+        if options.targeting.is_some() && options.royale.is_none() {
+            return Err(String::from("--targeting only goes with --royale"));
+        }
+
+        if options.versus && options.versus_bot.is_some() {
+            return Err(String::from("--versus can't go with --versus-bot"));
+        }
+        if (options.versus || options.versus_bot.is_some())
+            && (options.replay.is_some()
+                || options.resume
+                || options.record.is_some()
+                || options.trainer
+                || options.autoplay
+                || options.bot.is_some())
+        {
+            return Err(String::from(
+                "--versus or --versus-bot can't go with --replay, --resume, --record, --trainer, --autoplay or --bot",
+            ));
+        }
+
+        let network = options.host.is_some() || options.join.is_some();
+        if options.host.is_some() && options.join.is_some() {
+            return Err(String::from("--host can't go with --join"));
+        }
+        if options.join.is_some() && options.seed.is_some() {
+            return Err(String::from("--join plays by the seed of the host"));
+        }
+        if network
+            && (options.versus
+                || options.versus_bot.is_some()
+                || options.replay.is_some()
+                || options.resume
+                || options.record.is_some()
+                || options.trainer
+                || options.autoplay
+                || options.bot.is_some())
+        {
+            return Err(String::from(
+                "--host or --join can't go with --versus, --versus-bot, --replay, --resume, --record, --trainer, --autoplay or --bot",
+            ));
+        }
+
+        if options.broadcast.is_some()
+            && (options.replay.is_some()
+                || network
+                || options.versus
+                || options.versus_bot.is_some())
+        {
+            return Err(String::from(
+                "--broadcast can't go with --replay, --versus, --versus-bot, --host or --join",
+            ));
+        }
+        if options.spectate.is_some()
+            && (options.seed.is_some()
+                || options.record.is_some()
+                || options.replay.is_some()
+                || options.resume
+                || options.trainer
+                || options.autoplay
+                || options.bot.is_some()
+                || options.weights.is_some()
+                || options.versus
+                || options.versus_bot.is_some()
+                || network
+                || options.broadcast.is_some())
+        {
+            return Err(String::from(
+                "--spectate only watches, it goes with no other option",
+            ));
+        }
+
+        if options.serve.is_some()
+            && (options.record.is_some()
+                || options.replay.is_some()
+                || options.resume
+                || options.trainer
+                || options.autoplay
+                || options.bot.is_some()
+                || options.weights.is_some()
+                || options.versus
+                || options.versus_bot.is_some()
+                || network
+                || options.broadcast.is_some()
+                || options.spectate.is_some())
+        {
+            return Err(String::from(
+                "--serve only goes with --seed, the clients play the rest",
+            ));
+        }
+
+        if options.royale.is_some()
+            && (options.replay.is_some()
+                || options.resume
+                || options.record.is_some()
+                || options.trainer
+                || options.autoplay
+                || options.bot.is_some()
+                || options.versus
+                || network
+                || options.broadcast.is_some()
+                || options.spectate.is_some()
+                || options.serve.is_some())
+        {
+            return Err(String::from(
+                "--royale can't go with --replay, --resume, --record, --trainer, --autoplay, --bot, --versus, --host, --join, --broadcast, --spectate or --serve",
+            ));
+        }
+
+        Ok(options)
+    }
+}